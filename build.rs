@@ -0,0 +1,75 @@
+//! Compiles the contracts in `contracts/` whose bindings in `src/bindings`
+//! include their bytecode from `OUT_DIR`, so that it always comes from solc
+//! rather than from whatever `out/` a `forge build` left behind.
+//!
+//! Uses the `solc` on the path, or the one `SOLC` points at, with the settings
+//! in `foundry.toml`. Without one the crate still builds, with empty bytecode
+//! the workloads deploying these contracts refuse to run with.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Contracts compiled here, by source file and contract name.
+const CONTRACTS: &[(&str, &str)] = &[
+    ("contracts/compute.sol", "Compute"),
+    ("contracts/precompiles.sol", "Precompiles"),
+    ("contracts/relay.sol", "Relay"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SOLC");
+    println!("cargo:rerun-if-changed=foundry.toml");
+    for (source, _) in CONTRACTS {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    if let Err(e) = compile(&out) {
+        println!(
+            "cargo:warning=not compiling contracts/ ({}); install solc 0.8.19 or set SOLC to it",
+            e
+        );
+        for (_, name) in CONTRACTS {
+            write(&out, name, "bin", "");
+            write(&out, name, "bin-runtime", "");
+        }
+    }
+}
+
+fn compile(out: &Path) -> Result<(), String> {
+    let solc = env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+    let build = out.join("solc");
+    // forge's defaults, as `foundry.toml` leaves the optimizer alone
+    let output = Command::new(&solc)
+        .args(["--optimize", "--optimize-runs", "200"])
+        .args(["--metadata-hash", "none", "--no-cbor-metadata"])
+        .args(["--bin", "--bin-runtime", "--overwrite", "-o"])
+        .arg(&build)
+        .args(CONTRACTS.iter().map(|(source, _)| source))
+        .output()
+        .map_err(|e| format!("running {}: {}", solc, e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    for (_, name) in CONTRACTS {
+        for kind in ["bin", "bin-runtime"] {
+            let path = build.join(format!("{}.{}", name, kind));
+            let hex = fs::read_to_string(&path)
+                .map_err(|e| format!("reading {}: {}", path.display(), e))?;
+            write(out, name, kind, hex.trim());
+        }
+    }
+    Ok(())
+}
+
+/// Writes the bytecode in `hex` as raw bytes, for the bindings to
+/// `include_bytes!`.
+fn write(out: &Path, name: &str, kind: &str, hex: &str) {
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("solc wrote invalid hex"))
+        .collect();
+    fs::write(out.join(format!("{}.{}", name, kind)), bytes).unwrap();
+}
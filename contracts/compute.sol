@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+/// Loops of EVM work, each function doing `n` iterations of one kind of it,
+/// to tell the cost of executing opcodes apart from the cost of a call.
+contract Compute {
+    uint256 public rounds;
+    mapping(uint256 => uint256) public values;
+
+    /// Multiplies, adds and takes a remainder `n` times.
+    function arithmetic(uint256 n) public pure returns (uint256 x) {
+        x = 1;
+        unchecked {
+            for (uint256 i = 0; i < n; ++i) {
+                x = (x * 31 + i) % 1000000007;
+            }
+        }
+    }
+
+    /// Hashes a word `n` times, each hash being the next one's input. The
+    /// word is hashed in scratch space so that memory doesn't grow.
+    function hashing(uint256 n) public pure returns (bytes32 h) {
+        unchecked {
+            for (uint256 i = 0; i < n; ++i) {
+                assembly {
+                    mstore(0, h)
+                    h := keccak256(0, 32)
+                }
+            }
+        }
+    }
+
+    /// Allocates `n` words of memory and writes each of them.
+    function expandMemory(uint256 n) public pure returns (uint256) {
+        uint256[] memory words = new uint256[](n);
+        unchecked {
+            for (uint256 i = 0; i < n; ++i) {
+                words[i] = i;
+            }
+        }
+        return words.length;
+    }
+
+    /// Writes `n` storage slots, each to a value it didn't hold before.
+    function store(uint256 n) public {
+        uint256 round = ++rounds;
+        unchecked {
+            for (uint256 i = 0; i < n; ++i) {
+                values[i] = round;
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+/// Calls the EVM precompiles from a contract, the way protocols verifying
+/// signatures or proofs on-chain do.
+///
+/// The precompiles are at addresses 1 to 9: ecrecover, sha256, ripemd160,
+/// identity, modexp, bn256 addition, bn256 scalar multiplication, the bn256
+/// pairing check and blake2f.
+contract Precompiles {
+    /// Calls the precompile at `precompile` with `input`, returning what it
+    /// returns and reverting if it fails.
+    function run(
+        address precompile,
+        bytes calldata input
+    ) public view returns (bytes memory output) {
+        bool ok;
+        (ok, output) = precompile.staticcall(input);
+        require(ok);
+    }
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+/// A link in a chain of contracts, each calling the next, to benchmark
+/// nested calls by how deep they go.
+contract Relay {
+    /// The next link, or zero for the last one.
+    Relay public immutable next;
+    uint256 public hits;
+
+    constructor(Relay next_) {
+        next = next_;
+    }
+
+    /// Calls the link `depth` links down the chain, which counts a hit in
+    /// its own storage. Returns the hits it has counted.
+    function relayCall(uint256 depth) public returns (uint256) {
+        if (depth == 0) {
+            return ++hits;
+        }
+        return next.relayCall(depth - 1);
+    }
+
+    /// Delegatecalls the link `depth` links down the chain, whose code
+    /// counts a hit in the storage of the first link.
+    function relayDelegatecall(uint256 depth) public returns (uint256) {
+        if (depth == 0) {
+            return ++hits;
+        }
+        (bool ok, bytes memory result) = address(next).delegatecall(
+            abi.encodeCall(this.relayDelegatecall, (depth - 1))
+        );
+        require(ok);
+        return abi.decode(result, (uint256));
+    }
+
+    /// Staticcalls the link `depth` links down the chain, returning the
+    /// hits it has counted.
+    function relayStaticcall(uint256 depth) public view returns (uint256) {
+        if (depth == 0) {
+            return hits;
+        }
+        return next.relayStaticcall(depth - 1);
+    }
+}
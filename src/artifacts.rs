@@ -0,0 +1,337 @@
+use anyhow::{anyhow, bail, Context, Ok, Result};
+use async_trait::async_trait;
+use ethers::{
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Abi, Function, ParamType, StateMutability, Token,
+    },
+    contract::{Contract, ContractCall, ContractFactory},
+    providers::Middleware,
+    types::{Address, Bytes},
+    utils::id,
+};
+use serde::Deserialize;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{bench_functions::gas_used, workload::Workload};
+
+/// Placeholder argument for the client's default sender.
+pub const SENDER: &str = "$sender";
+
+/// The ABI and creation bytecode of a contract compiled by Foundry.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    pub name: String,
+    pub abi: Abi,
+    pub bytecode: Bytes,
+}
+
+#[derive(Deserialize)]
+struct ForgeArtifact {
+    abi: Abi,
+    bytecode: ForgeBytecode,
+}
+
+#[derive(Deserialize)]
+struct ForgeBytecode {
+    object: String,
+}
+
+impl Artifact {
+    /// Reads a single Foundry artifact, e.g. `out/Counter.sol/Counter.json`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| anyhow!("invalid artifact path: {}", path.display()))?
+            .to_string();
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read artifact {}", path.display()))?;
+        let artifact: ForgeArtifact = serde_json::from_str(&raw)
+            .with_context(|| format!("failed to parse artifact {}", path.display()))?;
+        let object = artifact.bytecode.object;
+        if object.contains("__$") {
+            bail!("{} has unlinked library references", name);
+        }
+        let bytecode: Bytes = object
+            .parse()
+            .with_context(|| format!("{} has invalid bytecode", name))?;
+        if bytecode.is_empty() {
+            bail!("{} has no bytecode, is it abstract or an interface?", name);
+        }
+        Ok(Self {
+            name,
+            abi: artifact.abi,
+            bytecode,
+        })
+    }
+
+    /// Deploys the contract, parsing `args` against the constructor's inputs.
+    pub async fn deploy<M: Middleware + 'static>(
+        &self,
+        client: Arc<M>,
+        args: &[String],
+    ) -> Result<Contract<M>> {
+        let inputs: Vec<ParamType> = self
+            .abi
+            .constructor()
+            .map(|constructor| constructor.inputs.iter().map(|p| p.kind.clone()).collect())
+            .unwrap_or_default();
+        let tokens = tokenize(&self.name, &inputs, args)?;
+        let factory = ContractFactory::new(self.abi.clone(), self.bytecode.clone(), client);
+        let contract = factory
+            .deploy_tokens(tokens)
+            .map_err(|e| anyhow!("failed to encode {} constructor: {}", self.name, e))?
+            .send()
+            .await
+            .map_err(|e| anyhow!("failed to deploy {}: {}", self.name, e))?;
+        Ok(contract)
+    }
+
+    /// Finds a function by full signature, e.g. `setNumber(uint256)`, or by
+    /// name when it is not overloaded.
+    pub fn function(&self, signature: &str) -> Result<&Function> {
+        if signature.contains('(') {
+            let selector = id(signature);
+            self.abi
+                .functions()
+                .find(|function| function.short_signature() == selector)
+                .ok_or_else(|| anyhow!("{} has no function {}", self.name, signature))
+        } else {
+            match self.abi.functions_by_name(signature)?.as_slice() {
+                [function] => Ok(function),
+                _ => bail!(
+                    "{} is overloaded in {}, use the full signature",
+                    signature,
+                    self.name
+                ),
+            }
+        }
+    }
+}
+
+/// The Foundry output directory, which holds one `<Source>.sol/<Contract>.json`
+/// artifact per compiled contract.
+#[derive(Clone, Debug)]
+pub struct Artifacts {
+    out: PathBuf,
+}
+
+impl Artifacts {
+    pub fn new(out: impl Into<PathBuf>) -> Self {
+        Self { out: out.into() }
+    }
+
+    /// Uses the `out` directory configured in `<root>/foundry.toml`, falling
+    /// back to Foundry's default of `out`.
+    pub fn from_foundry_project(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let config = root.join("foundry.toml");
+        let out = if config.exists() {
+            let raw = fs::read_to_string(&config)?;
+            let config: toml::Value = toml::from_str(&raw)
+                .with_context(|| format!("failed to parse {}", config.display()))?;
+            config
+                .get("profile")
+                .and_then(|profile| profile.get("default"))
+                .and_then(|default| default.get("out"))
+                .and_then(|out| out.as_str())
+                .unwrap_or("out")
+                .to_string()
+        } else {
+            "out".to_string()
+        };
+        Ok(Self::new(root.join(out)))
+    }
+
+    pub fn out(&self) -> &Path {
+        &self.out
+    }
+
+    /// Loads the artifact for a contract, e.g. `Counter`.
+    pub fn get(&self, contract: &str) -> Result<Artifact> {
+        let file = format!("{}.json", contract);
+        for source in self.sources()? {
+            let path = source.join(&file);
+            if path.exists() {
+                return Artifact::load(path);
+            }
+        }
+        bail!(
+            "no artifact for {} in {}, has `forge build` been run?",
+            contract,
+            self.out.display()
+        )
+    }
+
+    /// The names of every contract with an artifact in the output directory.
+    pub fn contracts(&self) -> Result<Vec<String>> {
+        let mut contracts = Vec::new();
+        for source in self.sources()? {
+            for entry in fs::read_dir(source)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                        contracts.push(stem.to_string());
+                    }
+                }
+            }
+        }
+        contracts.sort();
+        contracts.dedup();
+        Ok(contracts)
+    }
+
+    fn sources(&self) -> Result<Vec<PathBuf>> {
+        let entries = fs::read_dir(&self.out)
+            .with_context(|| format!("failed to read {}", self.out.display()))?;
+        let mut sources = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                sources.push(path);
+            }
+        }
+        sources.sort();
+        Ok(sources)
+    }
+}
+
+/// Benchmarks one function of a contract loaded from a Foundry artifact.
+///
+/// View and pure functions are benchmarked with `eth_call`, everything else is
+/// sent as a transaction and waits for its receipt. An argument of `$sender`
+/// is replaced with the client's default sender.
+pub struct ArtifactCall<M: Middleware + 'static> {
+    name: String,
+    artifact: Artifact,
+    constructor_args: Vec<String>,
+    signature: String,
+    args: Vec<String>,
+    call: Option<PreparedCall<M>>,
+}
+
+struct PreparedCall<M: Middleware + 'static> {
+    contract: Contract<M>,
+    selector: [u8; 4],
+    tokens: Vec<Token>,
+    read_only: bool,
+}
+
+impl<M: Middleware + 'static> ArtifactCall<M> {
+    pub fn new(
+        name: impl Into<String>,
+        artifact: Artifact,
+        signature: impl Into<String>,
+        args: Vec<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            artifact,
+            constructor_args: Vec::new(),
+            signature: signature.into(),
+            args,
+            call: None,
+        }
+    }
+
+    pub fn constructor_args(mut self, args: Vec<String>) -> Self {
+        self.constructor_args = args;
+        self
+    }
+
+    /// The call to make, and whether it's read only.
+    fn prepared(&self) -> Result<(ContractCall<M, Token>, bool)> {
+        let prepared = self
+            .call
+            .as_ref()
+            .ok_or_else(|| anyhow!("workload was run before setup"))?;
+        let call = prepared
+            .contract
+            .method_hash::<_, Token>(prepared.selector, prepared.tokens.as_slice())?;
+        Ok((call, prepared.read_only))
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for ArtifactCall<M> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        let sender = client.default_sender();
+        let function = self.artifact.function(&self.signature)?;
+        let inputs: Vec<ParamType> = function.inputs.iter().map(|p| p.kind.clone()).collect();
+        let args = substitute_sender(&self.args, sender)?;
+        let tokens = tokenize(&self.signature, &inputs, &args)?;
+        let selector = function.short_signature();
+        let read_only = matches!(
+            function.state_mutability,
+            StateMutability::View | StateMutability::Pure
+        );
+        let contract = self.artifact.deploy(client, &self.constructor_args).await?;
+        self.call = Some(PreparedCall {
+            contract,
+            selector,
+            tokens,
+            read_only,
+        });
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        let (call, read_only) = self.prepared()?;
+        if read_only {
+            call.call().await?;
+        } else {
+            call.send().await?.await?;
+        }
+        Ok(())
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let (call, read_only) = self.prepared()?;
+        if read_only {
+            Ok(Some(call.estimate_gas().await?.as_u64()))
+        } else {
+            Ok(gas_used(call.send().await?.await?))
+        }
+    }
+}
+
+fn substitute_sender(args: &[String], sender: Option<Address>) -> Result<Vec<String>> {
+    args.iter()
+        .map(|arg| match arg.as_str() {
+            SENDER => sender
+                .map(|sender| format!("{:?}", sender))
+                .ok_or_else(|| anyhow!("client has no default sender for {}", SENDER)),
+            _ => Ok(arg.clone()),
+        })
+        .collect()
+}
+
+fn tokenize(context: &str, inputs: &[ParamType], args: &[String]) -> Result<Vec<Token>> {
+    if inputs.len() != args.len() {
+        bail!(
+            "{} takes {} arguments but {} were given",
+            context,
+            inputs.len(),
+            args.len()
+        );
+    }
+    inputs
+        .iter()
+        .zip(args)
+        .map(|(kind, arg)| {
+            LenientTokenizer::tokenize(kind, arg)
+                .map_err(|e| anyhow!("invalid {} argument {:?} for {}: {}", kind, arg, context, e))
+        })
+        .collect()
+}
@@ -0,0 +1,565 @@
+//! The backends workloads are benchmarked against.
+//!
+//! A [`Backend`] builds a fresh middleware, along with whatever it talks to,
+//! and tears it down again once the run is over. Anything implementing it can
+//! be handed to [`crate::bench_backend`].
+
+use anyhow::{anyhow, bail, Context, Ok, Result};
+use arbiter_core::{
+    environment::{builder::EnvironmentBuilder, Environment},
+    middleware::RevmMiddleware,
+};
+use async_trait::async_trait;
+use ethers::{
+    core::{
+        k256::ecdsa::SigningKey,
+        utils::{Anvil, AnvilInstance},
+    },
+    middleware::SignerMiddleware,
+    providers::{Http, Ipc, JsonRpcClient, Middleware, Provider, Ws},
+    signers::{LocalWallet, Signer, Wallet},
+    types::{TransactionRequest, U256},
+    utils::keccak256,
+};
+use url::Url;
+
+use std::{
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    fixture::ChainState,
+    mock_node::MockNode,
+    network::{NetworkConditions, Proxy},
+    results::BackendInfo,
+};
+
+/// A factory for a middleware to benchmark.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    type Middleware: Middleware + 'static;
+
+    /// The label results are reported under, e.g. "Arbiter".
+    fn name(&self) -> &str;
+
+    /// How the middleware reaches the EVM, e.g. "in-process" or "http".
+    fn transport(&self) -> &str;
+
+    /// The simulated network between the middleware and the EVM, if any.
+    fn network(&self) -> Option<&NetworkConditions> {
+        None
+    }
+
+    /// Starts the backend and returns a middleware connected to it. Unless
+    /// the backend is connecting to something shared, each call starts from
+    /// fresh state.
+    async fn start(&mut self) -> Result<Arc<Self::Middleware>>;
+
+    /// Describes the running backend. By default the client is asked for its
+    /// chain id and version.
+    async fn info(&self, client: &Self::Middleware) -> BackendInfo {
+        BackendInfo {
+            transport: Some(self.transport().to_string()),
+            network: self.network().map(ToString::to_string),
+            ..BackendInfo::query(self.name(), client).await
+        }
+    }
+
+    /// Another middleware on the started backend, sending from an account
+    /// of its own named by `label`, for workloads with more than one sender.
+    /// `client` is the one [`Backend::start`] returned, which pays for the
+    /// account's gas where there is any, on a real network only if allowed to,
+    /// see [`RpcBackend::fund_accounts`].
+    async fn account(
+        &self,
+        _client: &Self::Middleware,
+        label: &str,
+    ) -> Result<Arc<Self::Middleware>> {
+        bail!("{} has no account for {:?}", self.name(), label)
+    }
+
+    /// Stops whatever [`Backend::start`] started.
+    async fn teardown(&mut self) -> Result<()>;
+}
+
+/// Arbiter's in-process revm [`Environment`].
+#[derive(Default)]
+pub struct ArbiterBackend {
+    state: Option<PathBuf>,
+    environment: Option<Environment>,
+}
+
+impl ArbiterBackend {
+    /// Starts from the [`ChainState`] saved at `path` instead of an empty
+    /// genesis.
+    pub fn load_state(mut self, path: impl Into<Option<PathBuf>>) -> Self {
+        self.state = path.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Backend for ArbiterBackend {
+    type Middleware = RevmMiddleware;
+
+    fn name(&self) -> &str {
+        "Arbiter"
+    }
+
+    fn transport(&self) -> &str {
+        "in-process"
+    }
+
+    async fn start(&mut self) -> Result<Arc<RevmMiddleware>> {
+        let mut builder = EnvironmentBuilder::new();
+        if let Some(path) = &self.state {
+            builder = builder.db(ChainState::load(path)?.to_db());
+        }
+        let environment = builder.build();
+        let client = RevmMiddleware::new(&environment, Some("name"))?;
+        // dropping the environment would take the client offline
+        self.environment = Some(environment);
+        Ok(client)
+    }
+
+    async fn account(&self, _client: &RevmMiddleware, label: &str) -> Result<Arc<RevmMiddleware>> {
+        let environment = self
+            .environment
+            .as_ref()
+            .ok_or_else(|| anyhow!("Arbiter isn't started"))?;
+        // gas is free, so there's nothing to fund
+        Ok(RevmMiddleware::new(environment, Some(label))?)
+    }
+
+    async fn teardown(&mut self) -> Result<()> {
+        if let Some(environment) = self.environment.take() {
+            environment.stop()?;
+        }
+        Ok(())
+    }
+}
+
+/// What a node account from [`Backend::account`] is topped up to, 100 ether.
+const ACCOUNT_FUNDS: u128 = 100_000_000_000_000_000_000;
+
+/// A signer for an account derived from `client`'s key and `label`, so that
+/// only whoever holds the key has it. With `fund` it's topped up by `client` to
+/// pay for gas, otherwise it has to hold some ether already.
+async fn funded_account<T: JsonRpcClient + Clone + 'static>(
+    client: &SignerMiddleware<Provider<T>, LocalWallet>,
+    label: &str,
+    fund: bool,
+) -> Result<Arc<SignerMiddleware<Provider<T>, LocalWallet>>> {
+    let seed = [&client.signer().signer().to_bytes()[..], label.as_bytes()].concat();
+    let wallet = LocalWallet::from_bytes(&keccak256(seed))?;
+    let wallet = wallet.with_chain_id(client.signer().chain_id());
+    let funds = U256::from(ACCOUNT_FUNDS);
+    let balance = client.get_balance(wallet.address(), None).await?;
+    if !fund && balance.is_zero() {
+        bail!(
+            "the {} account {:?} has no ether, send it some or set `fund_accounts = true` under [rpc]",
+            label,
+            wallet.address()
+        );
+    }
+    if fund && balance < funds {
+        let top_up = TransactionRequest::pay(wallet.address(), funds - balance);
+        client.send_transaction(top_up, None).await?.await?;
+    }
+    Ok(Arc::new(SignerMiddleware::new(
+        client.inner().clone(),
+        wallet,
+    )))
+}
+
+pub type AnvilMiddleware<T> = SignerMiddleware<Provider<T>, Wallet<SigningKey>>;
+pub type AnvilHttpMiddleware = AnvilMiddleware<Http>;
+
+/// A spawned Anvil node listening over HTTP and WebSocket on one port, and
+/// over IPC. The Anvil backends can share one so that every transport is
+/// benchmarked against the same chain. Dropping the last handle kills it.
+pub struct AnvilNode {
+    instance: AnvilInstance,
+    ipc: PathBuf,
+}
+
+impl AnvilNode {
+    pub fn spawn() -> Arc<Self> {
+        Self::spawn_from(None)
+    }
+
+    /// Spawns a node starting from the state saved at `state`, which Anvil
+    /// loads with `--load-state`.
+    pub fn spawn_from(state: Option<&Path>) -> Arc<Self> {
+        static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+        let ipc = std::env::temp_dir().join(format!(
+            "benchlayer-anvil-{}-{}.ipc",
+            std::process::id(),
+            SPAWNED.fetch_add(1, Ordering::Relaxed)
+        ));
+        // No blocktime mines a new block for each tx, which is fastest.
+        let mut anvil = Anvil::new().arg("--ipc").arg(ipc.to_string_lossy());
+        if let Some(state) = state {
+            anvil = anvil.arg("--load-state").arg(state.to_string_lossy());
+        }
+        let instance = anvil.spawn();
+        Arc::new(Self { instance, ipc })
+    }
+
+    pub fn instance(&self) -> &AnvilInstance {
+        &self.instance
+    }
+
+    pub fn ipc_path(&self) -> &Path {
+        &self.ipc
+    }
+}
+
+impl Drop for AnvilNode {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.ipc);
+    }
+}
+
+/// A way of connecting to an [`AnvilNode`].
+#[async_trait]
+pub trait AnvilTransport: JsonRpcClient + Clone + Sized + 'static {
+    /// The label results are reported under, e.g. "Anvil WS".
+    const LABEL: &'static str;
+    /// The transport's name, e.g. "ws".
+    const NAME: &'static str;
+
+    /// Connects to `node`, or to `proxy` in front of it when given.
+    async fn connect(node: &AnvilNode, proxy: Option<&Proxy>) -> Result<Self>;
+}
+
+#[async_trait]
+impl AnvilTransport for Http {
+    const LABEL: &'static str = "Anvil";
+    const NAME: &'static str = "http";
+
+    async fn connect(node: &AnvilNode, proxy: Option<&Proxy>) -> Result<Self> {
+        let url = match proxy {
+            Some(proxy) => format!("http://{}", proxy.addr()),
+            None => node.instance.endpoint(),
+        };
+        Ok(Http::from_str(&url)?)
+    }
+}
+
+#[async_trait]
+impl AnvilTransport for Ws {
+    const LABEL: &'static str = "Anvil WS";
+    const NAME: &'static str = "ws";
+
+    async fn connect(node: &AnvilNode, proxy: Option<&Proxy>) -> Result<Self> {
+        let url = match proxy {
+            Some(proxy) => format!("ws://{}", proxy.addr()),
+            None => node.instance.ws_endpoint(),
+        };
+        Ok(Ws::connect(url).await?)
+    }
+}
+
+#[async_trait]
+impl AnvilTransport for Ipc {
+    const LABEL: &'static str = "Anvil IPC";
+    const NAME: &'static str = "ipc";
+
+    async fn connect(node: &AnvilNode, proxy: Option<&Proxy>) -> Result<Self> {
+        if proxy.is_some() {
+            bail!("network conditions can't be simulated over IPC");
+        }
+        Ok(Ipc::connect(&node.ipc).await?)
+    }
+}
+
+/// Anvil over one of its transports, signing with the first dev account. It
+/// spawns its own node unless given one to share with [`AnvilBackend::on`].
+pub struct AnvilBackend<T: AnvilTransport> {
+    shared: Option<Arc<AnvilNode>>,
+    state: Option<PathBuf>,
+    network: Option<NetworkConditions>,
+    node: Option<Arc<AnvilNode>>,
+    proxy: Option<Proxy>,
+    transport: PhantomData<T>,
+}
+
+pub type AnvilHttpBackend = AnvilBackend<Http>;
+pub type AnvilWsBackend = AnvilBackend<Ws>;
+pub type AnvilIpcBackend = AnvilBackend<Ipc>;
+
+impl<T: AnvilTransport> Default for AnvilBackend<T> {
+    fn default() -> Self {
+        Self {
+            shared: None,
+            state: None,
+            network: None,
+            node: None,
+            proxy: None,
+            transport: PhantomData,
+        }
+    }
+}
+
+impl<T: AnvilTransport> AnvilBackend<T> {
+    /// Connects to `node` instead of spawning a node of its own.
+    pub fn on(node: Arc<AnvilNode>) -> Self {
+        Self {
+            shared: Some(node),
+            ..Self::default()
+        }
+    }
+
+    /// Connects through a [`Proxy`] simulating `network`.
+    pub fn through(mut self, network: impl Into<Option<NetworkConditions>>) -> Self {
+        self.network = network.into();
+        self
+    }
+
+    /// Spawns its node from the [`ChainState`] saved at `path`. A shared
+    /// node has to be spawned from it with [`AnvilNode::spawn_from`].
+    pub fn load_state(mut self, path: impl Into<Option<PathBuf>>) -> Self {
+        self.state = path.into();
+        self
+    }
+}
+
+#[async_trait]
+impl<T: AnvilTransport> Backend for AnvilBackend<T> {
+    type Middleware = AnvilMiddleware<T>;
+
+    fn name(&self) -> &str {
+        T::LABEL
+    }
+
+    fn transport(&self) -> &str {
+        T::NAME
+    }
+
+    fn network(&self) -> Option<&NetworkConditions> {
+        self.network.as_ref()
+    }
+
+    async fn start(&mut self) -> Result<Arc<AnvilMiddleware<T>>> {
+        let node = match &self.shared {
+            Some(node) => node.clone(),
+            None => AnvilNode::spawn_from(self.state.as_deref()),
+        };
+        self.proxy = match self.network {
+            Some(network) => Some(Proxy::spawn(
+                format!("127.0.0.1:{}", node.instance.port()),
+                network,
+            )?),
+            None => None,
+        };
+        let transport = T::connect(&node, self.proxy.as_ref()).await?;
+        let provider = Provider::new(transport).interval(Duration::ZERO);
+        let wallet: LocalWallet = node.instance.keys()[0].clone().into();
+        let client = Arc::new(SignerMiddleware::new(
+            provider,
+            wallet.with_chain_id(node.instance.chain_id()),
+        ));
+        self.node = Some(node);
+        Ok(client)
+    }
+
+    async fn account(
+        &self,
+        client: &AnvilMiddleware<T>,
+        label: &str,
+    ) -> Result<Arc<AnvilMiddleware<T>>> {
+        funded_account(client, label, true).await
+    }
+
+    async fn teardown(&mut self) -> Result<()> {
+        self.proxy.take();
+        // the node is killed once nothing else shares it
+        self.node.take();
+        Ok(())
+    }
+}
+
+pub type RpcMiddleware = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Any node reachable over HTTP, signing with the given key. Nothing is
+/// started or stopped, so state carries over between runs.
+pub struct RpcBackend {
+    url: String,
+    key: String,
+    chain_id: Option<u64>,
+    poll_interval: Duration,
+    fund_accounts: bool,
+    network: Option<NetworkConditions>,
+    proxy: Option<Proxy>,
+}
+
+impl RpcBackend {
+    /// Connects to `url`, signing with the hex private `key`.
+    pub fn new(url: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            key: key.into(),
+            chain_id: None,
+            poll_interval: Duration::from_millis(100),
+            fund_accounts: false,
+            network: None,
+            proxy: None,
+        }
+    }
+
+    /// The chain id to sign for. Without one the node is asked for it.
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// How often pending transactions are polled for a receipt, 100ms by
+    /// default.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Lets [`Backend::account`] top the accounts it derives up to 100 ether
+    /// from the signer's. Without it they have to be funded beforehand, as
+    /// it's real ether on anything but a dev chain.
+    pub fn fund_accounts(mut self, fund_accounts: bool) -> Self {
+        self.fund_accounts = fund_accounts;
+        self
+    }
+
+    /// Connects through a [`Proxy`] simulating `network`. Only plain `http`
+    /// urls can be proxied.
+    pub fn through(mut self, network: impl Into<Option<NetworkConditions>>) -> Self {
+        self.network = network.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Backend for RpcBackend {
+    type Middleware = RpcMiddleware;
+
+    fn name(&self) -> &str {
+        "RPC"
+    }
+
+    fn transport(&self) -> &str {
+        "http"
+    }
+
+    fn network(&self) -> Option<&NetworkConditions> {
+        self.network.as_ref()
+    }
+
+    async fn start(&mut self) -> Result<Arc<RpcMiddleware>> {
+        let mut url =
+            Url::parse(&self.url).with_context(|| format!("invalid RPC url {:?}", self.url))?;
+        if let Some(network) = self.network {
+            if url.scheme() != "http" {
+                bail!("network conditions can only be simulated for http urls");
+            }
+            let upstream = format!(
+                "{}:{}",
+                url.host_str().unwrap_or("localhost"),
+                url.port_or_known_default().unwrap_or(80)
+            );
+            let proxy = Proxy::spawn(upstream, network)?;
+            url.set_ip_host(proxy.addr().ip()).unwrap();
+            url.set_port(Some(proxy.addr().port())).unwrap();
+            self.proxy = Some(proxy);
+        }
+        let http = Http::new(url);
+        let provider = Provider::new(http).interval(self.poll_interval);
+        let wallet = LocalWallet::from_str(&self.key).context("invalid signer key")?;
+        let chain_id = match self.chain_id {
+            Some(chain_id) => chain_id,
+            None => provider
+                .get_chainid()
+                .await
+                .with_context(|| format!("failed to reach {}", self.url))?
+                .as_u64(),
+        };
+        Ok(Arc::new(SignerMiddleware::new(
+            provider,
+            wallet.with_chain_id(chain_id),
+        )))
+    }
+
+    async fn account(&self, client: &RpcMiddleware, label: &str) -> Result<Arc<RpcMiddleware>> {
+        funded_account(client, label, self.fund_accounts).await
+    }
+
+    async fn teardown(&mut self) -> Result<()> {
+        self.proxy.take();
+        Ok(())
+    }
+}
+
+/// An [`RpcBackend`] connected to a fresh [`MockNode`], which answers every
+/// request without executing anything.
+#[derive(Default)]
+pub struct MockBackend {
+    network: Option<NetworkConditions>,
+    node: Option<MockNode>,
+    rpc: Option<RpcBackend>,
+}
+
+impl MockBackend {
+    /// Connects through a [`Proxy`] simulating `network`.
+    pub fn through(mut self, network: impl Into<Option<NetworkConditions>>) -> Self {
+        self.network = network.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Backend for MockBackend {
+    type Middleware = RpcMiddleware;
+
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn transport(&self) -> &str {
+        "http"
+    }
+
+    fn network(&self) -> Option<&NetworkConditions> {
+        self.network.as_ref()
+    }
+
+    async fn start(&mut self) -> Result<Arc<RpcMiddleware>> {
+        let node = MockNode::spawn()?;
+        let mut rpc = RpcBackend::new(node.url(), MockNode::KEY)
+            .chain_id(MockNode::CHAIN_ID)
+            .poll_interval(Duration::ZERO)
+            .through(self.network);
+        let client = rpc.start().await?;
+        self.node = Some(node);
+        self.rpc = Some(rpc);
+        Ok(client)
+    }
+
+    async fn account(&self, client: &RpcMiddleware, label: &str) -> Result<Arc<RpcMiddleware>> {
+        // the mock node's ether isn't worth anything
+        funded_account(client, label, true).await
+    }
+
+    async fn teardown(&mut self) -> Result<()> {
+        if let Some(mut rpc) = self.rpc.take() {
+            rpc.teardown().await?;
+        }
+        self.node.take();
+        Ok(())
+    }
+}
@@ -3,15 +3,46 @@ use arbiter_core::bindings::{
     arbiter_token::{self, ArbiterToken},
 };
 
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, bail, Ok, Result};
+use async_trait::async_trait;
 use ethers::{
     providers::Middleware,
-    types::{Address, I256, U256},
+    types::{Address, TransactionReceipt, H256, I256, U256},
+    utils::keccak256,
 };
 
 use std::sync::Arc;
 
-use crate::bindings;
+use crate::{bindings, utils, workload::Workload};
+
+/// Storage slot of `totalSupply` in solmate's ERC20, which `ArbiterToken`
+/// extends.
+pub(crate) const TOTAL_SUPPLY_SLOT: u64 = 2;
+/// Storage slot of the `balanceOf` mapping in solmate's ERC20.
+pub(crate) const BALANCE_OF_SLOT: u64 = 3;
+
+/// Where an `ArbiterToken` stores the balance of `owner`.
+pub(crate) fn balance_of_slot(owner: Address) -> H256 {
+    let mut preimage = [0; 64];
+    preimage[12..32].copy_from_slice(owner.as_bytes());
+    preimage[32..].copy_from_slice(H256::from_low_u64_be(BALANCE_OF_SLOT).as_bytes());
+    H256::from(keccak256(preimage))
+}
+
+/// Storage slot of the `allowance` mapping in solmate's ERC20.
+pub(crate) const ALLOWANCE_SLOT: u64 = 4;
+
+/// Where an `ArbiterToken` stores how much `spender` may spend of `owner`'s
+/// balance.
+pub(crate) fn allowance_slot(owner: Address, spender: Address) -> H256 {
+    let mut preimage = [0; 64];
+    preimage[12..32].copy_from_slice(owner.as_bytes());
+    preimage[32..].copy_from_slice(H256::from_low_u64_be(ALLOWANCE_SLOT).as_bytes());
+    let inner = keccak256(preimage);
+    preimage[12..32].copy_from_slice(spender.as_bytes());
+    preimage[32..].copy_from_slice(&inner);
+    H256::from(keccak256(preimage))
+}
 
 pub(crate) async fn lookup<M: Middleware + 'static>(token: ArbiterToken<M>) -> Result<()> {
     let address = token.client().default_sender().unwrap();
@@ -43,3 +74,261 @@ pub(crate) async fn stateful_call<M: Middleware + 'static>(
     arbiter_token.mint(mint_address, wad).send().await?.await?;
     Ok(())
 }
+
+pub struct StatefulCall<M: Middleware + 'static> {
+    token: Option<ArbiterToken<M>>,
+    mint_address: Address,
+}
+
+impl<M: Middleware + 'static> Default for StatefulCall<M> {
+    fn default() -> Self {
+        Self {
+            token: None,
+            mint_address: Address::zero(),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for StatefulCall<M> {
+    fn name(&self) -> &str {
+        "Stateful Call"
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        self.mint_address = client
+            .default_sender()
+            .ok_or_else(|| anyhow!("client has no default sender"))?;
+        self.token = Some(utils::deploy_token(client).await?);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        stateful_call(deployed(&self.token)?.clone(), self.mint_address).await
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let wad = U256::from(10_u128.pow(18));
+        let mint = deployed(&self.token)?.mint(self.mint_address, wad);
+        let receipt = mint.send().await?.await?;
+        Ok(gas_used(receipt))
+    }
+
+    fn written_slots(&self) -> Vec<(Address, H256)> {
+        let Some(token) = &self.token else {
+            return Vec::new();
+        };
+        vec![
+            (token.address(), H256::from_low_u64_be(TOTAL_SUPPLY_SLOT)),
+            (token.address(), balance_of_slot(self.mint_address)),
+        ]
+    }
+}
+
+pub struct StatelessCall<M: Middleware + 'static> {
+    math: Option<ArbiterMath<M>>,
+}
+
+impl<M: Middleware + 'static> Default for StatelessCall<M> {
+    fn default() -> Self {
+        Self { math: None }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for StatelessCall<M> {
+    fn name(&self) -> &str {
+        "Stateless Call"
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        self.math = Some(utils::deploy_math(client).await?);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        stateless_call(deployed(&self.math)?.clone()).await
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let iwad = I256::from(10_u128.pow(18));
+        let cdf = deployed(&self.math)?.cdf(iwad);
+        Ok(Some(cdf.estimate_gas().await?.as_u64()))
+    }
+}
+
+pub struct Create<M: Middleware + 'static> {
+    client: Option<Arc<M>>,
+}
+
+impl<M: Middleware + 'static> Default for Create<M> {
+    fn default() -> Self {
+        Self { client: None }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for Create<M> {
+    fn name(&self) -> &str {
+        "Create"
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        self.client = Some(client);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        create_call(deployed(&self.client)?.clone()).await
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let (_, receipt) = bindings::counter::Counter::deploy(deployed(&self.client)?.clone(), ())?
+            .send_with_receipt()
+            .await?;
+        Ok(gas_used(Some(receipt)))
+    }
+}
+
+pub struct Lookups<M: Middleware + 'static> {
+    token: Option<ArbiterToken<M>>,
+}
+
+impl<M: Middleware + 'static> Default for Lookups<M> {
+    fn default() -> Self {
+        Self { token: None }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for Lookups<M> {
+    fn name(&self) -> &str {
+        "Lookups"
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        self.token = Some(utils::deploy_token(client).await?);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        lookup(deployed(&self.token)?.clone()).await
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let token = deployed(&self.token)?;
+        let address = token.client().default_sender().unwrap();
+        Ok(Some(
+            token.balance_of(address).estimate_gas().await?.as_u64(),
+        ))
+    }
+}
+
+/// Approves a spender, which then transfers the allowance on with
+/// `transferFrom` from an account of its own, on each of two tokens, so that
+/// every iteration calls into two contracts from two senders and writes an
+/// allowance as well as balances.
+pub struct TransferFrom<M: Middleware + 'static> {
+    spender: Arc<M>,
+    /// Each token as seen by the owner, then by the spender.
+    tokens: Vec<(ArbiterToken<M>, ArbiterToken<M>)>,
+    owner: Address,
+    spender_account: Address,
+    recipient: Address,
+}
+
+impl<M: Middleware + 'static> TransferFrom<M> {
+    /// Sends the `transferFrom`s through `spender`, whose account has to
+    /// differ from the one the workload is set up with.
+    pub fn new(spender: Arc<M>) -> Self {
+        Self {
+            spender,
+            tokens: Vec::new(),
+            owner: Address::zero(),
+            spender_account: Address::zero(),
+            recipient: Address::repeat_byte(0xbe),
+        }
+    }
+
+    /// Sends the approval and transfer on each token, returning their
+    /// receipts.
+    async fn transfer(&self) -> Result<Vec<Option<TransactionReceipt>>> {
+        if self.tokens.is_empty() {
+            return Err(anyhow!("workload was run before setup"));
+        }
+        let wad = U256::from(10_u128.pow(18));
+        let mut receipts = Vec::new();
+        for (owned, spent) in &self.tokens {
+            let approve = owned.approve(self.spender_account, wad);
+            receipts.push(approve.send().await?.await?);
+            let transfer = spent.transfer_from(self.owner, self.recipient, wad);
+            receipts.push(transfer.send().await?.await?);
+        }
+        Ok(receipts)
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for TransferFrom<M> {
+    fn name(&self) -> &str {
+        "Transfer From"
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        self.owner = client
+            .default_sender()
+            .ok_or_else(|| anyhow!("client has no default sender"))?;
+        self.spender_account = self
+            .spender
+            .default_sender()
+            .ok_or_else(|| anyhow!("spender has no default sender"))?;
+        if self.spender_account == self.owner {
+            bail!("the spender has to send from an account other than the owner's");
+        }
+        self.tokens.clear();
+        for _ in 0..2 {
+            let token = utils::deploy_token(client.clone()).await?;
+            // enough never to run out, however many iterations there are
+            token.mint(self.owner, U256::MAX).send().await?.await?;
+            let spent = ArbiterToken::new(token.address(), self.spender.clone());
+            self.tokens.push((token, spent));
+        }
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.transfer().await?;
+        Ok(())
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let receipts = self.transfer().await?;
+        Ok(receipts.into_iter().map(gas_used).sum())
+    }
+
+    fn written_slots(&self) -> Vec<(Address, H256)> {
+        self.tokens
+            .iter()
+            .flat_map(|(token, _)| {
+                [
+                    (token.address(), balance_of_slot(self.owner)),
+                    (token.address(), balance_of_slot(self.recipient)),
+                    (
+                        token.address(),
+                        allowance_slot(self.owner, self.spender_account),
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn gas_used(receipt: Option<TransactionReceipt>) -> Option<u64> {
+    Some(receipt?.gas_used?.as_u64())
+}
+
+pub(crate) fn deployed<T>(state: &Option<T>) -> Result<&T> {
+    state
+        .as_ref()
+        .ok_or_else(|| anyhow!("workload was run before setup"))
+}
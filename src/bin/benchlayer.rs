@@ -0,0 +1,634 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use criterion::Criterion;
+use ether_bench::{
+    artifacts::Artifacts,
+    backend::{AnvilHttpBackend, Backend},
+    compare::{CompareOptions, Comparison},
+    compute::{ComputeReport, ComputeSettings},
+    depth::{DepthReport, DepthSettings},
+    fixture::{dump, populate, FixtureSettings},
+    instrumented::{CallReport, CallSettings},
+    network::NetworkConditions,
+    open_loop::{OpenLoopReport, OpenLoopSettings},
+    phases::{PhaseReport, PhaseSettings},
+    regression::{baseline_path, Gate, Threshold},
+    report::Report,
+    scaling::{ScalingReport, ScalingSettings},
+    snapshot::Reset,
+    stack::StackReport,
+    suite::{backend_name, Suite, BACKENDS},
+    throughput::{ThroughputReport, ThroughputSettings},
+    verify::{VerifyReport, VerifySettings},
+    workload::{BUILTIN_WORKLOADS, PRECOMPILE_WORKLOADS, TRANSFER_WORKLOADS},
+};
+use std::path::{Path, PathBuf};
+
+/// Exit code of a run that regressed against its baseline, distinct from the 1
+/// of any other error and clap's 2 for bad arguments.
+const REGRESSION_EXIT_CODE: i32 = 3;
+/// Exit code of a `verify` that found the backends disagreeing.
+const MISMATCH_EXIT_CODE: i32 = 4;
+
+#[derive(Parser)]
+#[command(
+    name = "benchlayer",
+    about = "Benchmarking for rust-ethereum middleware"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Benchmark a suite, or the built-in workloads, against some backends.
+    Run(Box<RunArgs>),
+    /// Show the available workloads and backends.
+    List {
+        /// Foundry project whose compiled contracts should be listed.
+        #[arg(long, default_value = ".")]
+        project: PathBuf,
+    },
+    /// Diff two saved runs, each a baseline name or a `.json` results file.
+    Compare {
+        baseline: String,
+        other: String,
+        /// How much slower a benchmark may get before it's marked regressed.
+        #[arg(long, default_value = "5%")]
+        threshold: Threshold,
+        #[arg(long, default_value = "target/benchlayer/baselines")]
+        baseline_dir: PathBuf,
+    },
+    /// Compare the backends in a results file written by `run --output`.
+    Speedup {
+        results: PathBuf,
+        /// The backend to compare the others against.
+        #[arg(long)]
+        baseline: String,
+        /// Confidence level of the bootstrapped intervals.
+        #[arg(long, default_value_t = 0.95)]
+        confidence: f64,
+        #[arg(long, default_value_t = 10_000)]
+        resamples: usize,
+    },
+    /// Check that backends end up with the same gas, logs, return data and
+    /// state after the same transactions, diffing each against the first.
+    Verify {
+        /// Suite whose `[rpc]` and `[network]` apply. Its workloads don't.
+        #[arg(long)]
+        suite: Option<PathBuf>,
+        /// Backends to check. Defaults to `arbiter` and `anvil`.
+        #[arg(long = "backend")]
+        backends: Vec<String>,
+        /// Mints to send before reading the state back.
+        #[arg(long, default_value_t = VerifySettings::default().mints)]
+        mints: usize,
+        /// Write the observations and differences to this file, formatted
+        /// by its extension: `.json` or `.md`. Can be given more than once.
+        #[arg(long = "output")]
+        outputs: Vec<PathBuf>,
+    },
+    /// Build a chain state on Anvil with funded accounts and token balances,
+    /// and save it for `run --state` to start from.
+    Fixture {
+        output: PathBuf,
+        #[arg(long, default_value_t = FixtureSettings::default().accounts)]
+        accounts: usize,
+        #[arg(long, default_value_t = FixtureSettings::default().tokens)]
+        tokens: usize,
+    },
+    /// Render a saved run, a baseline name or a `.json` results file.
+    Report {
+        baseline: String,
+        #[arg(long, default_value = "target/benchlayer/baselines")]
+        baseline_dir: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Suite file to run. Without one the built-in workloads are run.
+    #[arg(long)]
+    suite: Option<PathBuf>,
+    /// Backends to run against, overriding the suite's. Defaults to all,
+    /// with `rpc` only if the suite configures it.
+    #[arg(long = "backend")]
+    backends: Vec<String>,
+    /// Built-in, precompile or transfer workloads to run when no suite is
+    /// given. Defaults to all of the built-in ones.
+    #[arg(long = "workload")]
+    workloads: Vec<String>,
+    #[arg(long)]
+    sample_size: Option<usize>,
+    /// Seconds.
+    #[arg(long)]
+    measurement_time: Option<f64>,
+    /// Seconds.
+    #[arg(long)]
+    warm_up_time: Option<f64>,
+    /// Save the results under this name for `compare`, `report` and
+    /// `--check-baseline`.
+    #[arg(long)]
+    save_baseline: Option<String>,
+    #[arg(long, default_value = "target/criterion")]
+    criterion_dir: PathBuf,
+    /// Fail if any benchmark regressed against the baseline saved under
+    /// this name.
+    #[arg(long)]
+    check_baseline: Option<String>,
+    /// How much slower a benchmark may get, either a percentage like `5%`
+    /// or `significant[:confidence]` for a bootstrap test.
+    #[arg(long, default_value = "5%")]
+    threshold: Threshold,
+    #[arg(long, default_value = "target/benchlayer/baselines")]
+    baseline_dir: PathBuf,
+    /// Write the results to this file, formatted by its extension: `.json`,
+    /// `.csv` or `.md`. Can be given more than once.
+    #[arg(long = "output")]
+    outputs: Vec<PathBuf>,
+    /// Print the speedup of every other backend over this one.
+    #[arg(long)]
+    speedup_over: Option<String>,
+    /// Measure throughput with this many calls in flight instead of running
+    /// Criterion, e.g. `--concurrency 1,8,64`. The measurement and warm-up
+    /// times apply to each level.
+    #[arg(long, value_delimiter = ',', conflicts_with = "rate")]
+    concurrency: Vec<usize>,
+    /// Start this many calls per second, whether or not earlier ones have
+    /// returned, instead of running Criterion. Runs for the measurement time.
+    #[arg(long, conflicts_with = "phases")]
+    rate: Option<f64>,
+    /// Time each phase of a minting transaction separately instead of
+    /// running Criterion, sending the sample size's worth of transactions.
+    #[arg(long, conflicts_with = "concurrency")]
+    phases: bool,
+    /// Count and time the middleware calls each workload makes instead of
+    /// running Criterion, running each the sample size's number of times.
+    #[arg(long, conflicts_with_all = ["concurrency", "rate", "phases"])]
+    calls: bool,
+    /// Benchmark each backend bare and again after adding each of the gas
+    /// oracle, signer and nonce manager middleware layers, reporting what
+    /// each one adds.
+    #[arg(long, conflicts_with_all = ["concurrency", "rate", "phases", "calls"])]
+    layers: bool,
+    /// Benchmark lookups and mints against a token with each of these
+    /// numbers of holders instead of running the workloads, e.g.
+    /// `--state-sizes 1000,10000,100000`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = ["concurrency", "rate", "phases", "calls", "layers", "reset"]
+    )]
+    state_sizes: Vec<usize>,
+    /// Benchmark the compute contract's arithmetic, hashing, memory and
+    /// storage loops at each of these loop counts instead of running the
+    /// workloads, e.g. `--loops 1,10,100,1000`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = [
+            "concurrency", "rate", "phases", "calls", "layers", "state_sizes", "reset"
+        ]
+    )]
+    loops: Vec<u64>,
+    /// Benchmark calls, delegatecalls and staticcalls nested through a chain
+    /// of contracts at each of these depths instead of running the
+    /// workloads, e.g. `--depths 0,1,2,4,8,16,32`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = [
+            "concurrency", "rate", "phases", "calls", "layers", "state_sizes", "loops", "reset"
+        ]
+    )]
+    depths: Vec<usize>,
+    /// Put the state back to how it was after setup before every
+    /// `iteration` or `batch` Criterion times, overriding the suite's.
+    #[arg(long, conflicts_with_all = ["concurrency", "rate", "phases", "calls", "layers"])]
+    reset: Option<Reset>,
+    /// Start Arbiter and Anvil from a state written by `fixture`, overriding
+    /// the suite's.
+    #[arg(long)]
+    state: Option<PathBuf>,
+    /// Milliseconds of simulated latency each way to every backend reached
+    /// over TCP, overriding the suite's `[network]`.
+    #[arg(long)]
+    latency: Option<f64>,
+    /// Up to this many milliseconds of extra latency, picked at random.
+    #[arg(long)]
+    jitter: Option<f64>,
+    /// Simulated bandwidth each way, in kilobits per second.
+    #[arg(long)]
+    bandwidth: Option<f64>,
+    /// Fraction of writes that stall as if lost, e.g. `0.01`.
+    #[arg(long)]
+    drop_rate: Option<f64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Run(args) => run(*args).await?,
+        Command::List { project } => {
+            println!("Backends:");
+            for backend in BACKENDS {
+                println!("  {}", backend);
+            }
+            println!("Workloads:");
+            for workload in BUILTIN_WORKLOADS {
+                println!("  {}", workload);
+            }
+            println!("Precompile workloads:");
+            for workload in PRECOMPILE_WORKLOADS {
+                println!("  {}", workload);
+            }
+            println!("Transfer workloads, sent from two accounts:");
+            for workload in TRANSFER_WORKLOADS {
+                println!("  {}", workload);
+            }
+            let artifacts = Artifacts::from_foundry_project(&project)?;
+            if let Ok(contracts) = artifacts.contracts() {
+                println!("Contracts in {}:", artifacts.out().display());
+                for contract in contracts {
+                    println!("  {}", contract);
+                }
+            }
+        }
+        Command::Compare {
+            baseline,
+            other,
+            threshold,
+            baseline_dir,
+        } => {
+            let before = load_saved(&baseline_dir, &baseline)?;
+            let after = load_saved(&baseline_dir, &other)?;
+            let gate = Gate::new(&before, &after, threshold);
+            print!("{}", gate.to_markdown());
+            for label in &gate.unmatched {
+                println!("{} isn't in {}", label, baseline);
+            }
+            for label in &gate.missing {
+                println!("{} isn't in {}", label, other);
+            }
+        }
+        Command::Speedup {
+            results,
+            baseline,
+            confidence,
+            resamples,
+        } => {
+            let report = Report::load(results)?;
+            // a backend's key, like `anvil-ws`, or the name it's reported under
+            let baseline = backend_name(&baseline).unwrap_or(&baseline);
+            let options = CompareOptions {
+                resamples,
+                confidence,
+                ..Default::default()
+            };
+            print!(
+                "{}",
+                Comparison::new(&report, baseline, options)?.to_markdown()
+            );
+        }
+        Command::Verify {
+            suite,
+            backends,
+            mints,
+            outputs,
+        } => {
+            let mut suite = match suite {
+                // only its backends, [rpc] and [network] apply
+                Some(path) => Suite {
+                    workloads: Vec::new(),
+                    contracts: Vec::new(),
+                    benchmarks: Vec::new(),
+                    ..Suite::load(path)?
+                },
+                None => Suite::builtin(Vec::new(), Vec::new()),
+            };
+            suite.backends = if backends.is_empty() {
+                vec!["arbiter".to_string(), "anvil".to_string()]
+            } else {
+                backends
+            };
+            suite.validate_backends()?;
+            if suite.backends.len() < 2 {
+                bail!("verify needs at least two backends to compare");
+            }
+            let settings = VerifySettings { mints };
+            settings.validate()?;
+            let report = VerifyReport::new(suite.verify(&settings).await?);
+            print!("{}", report.to_markdown());
+            for output in outputs {
+                report.save(&output)?;
+                println!("Wrote {}", output.display());
+            }
+            if !report.passed() {
+                std::process::exit(MISMATCH_EXIT_CODE);
+            }
+        }
+        Command::Fixture {
+            output,
+            accounts,
+            tokens,
+        } => {
+            let settings = FixtureSettings {
+                accounts,
+                tokens,
+                ..Default::default()
+            };
+            settings.validate()?;
+            let mut anvil = AnvilHttpBackend::default();
+            let client = anvil.start().await?;
+            let tokens = populate(client.clone(), &settings).await?;
+            let state = dump(&*client).await?;
+            anvil.teardown().await?;
+            state.save(&output)?;
+            for (index, token) in tokens.iter().enumerate() {
+                println!("Token {} is at {:?}", index, token);
+            }
+            println!(
+                "Wrote {} accounts to {}",
+                state.accounts.len(),
+                output.display()
+            );
+        }
+        Command::Report {
+            baseline,
+            baseline_dir,
+        } => {
+            print!("{}", load_saved(&baseline_dir, &baseline)?.to_markdown());
+        }
+    }
+    Ok(())
+}
+
+async fn run(args: RunArgs) -> Result<()> {
+    let RunArgs {
+        suite,
+        backends,
+        workloads,
+        sample_size,
+        measurement_time,
+        warm_up_time,
+        save_baseline,
+        criterion_dir,
+        check_baseline,
+        threshold,
+        baseline_dir,
+        outputs,
+        speedup_over,
+        concurrency,
+        rate,
+        phases,
+        calls,
+        layers,
+        state_sizes,
+        loops,
+        depths,
+        reset,
+        state,
+        latency,
+        jitter,
+        bandwidth,
+        drop_rate,
+    } = args;
+    let mut suite = match suite {
+        Some(path) => {
+            if !workloads.is_empty() {
+                bail!("--workload can't be combined with --suite");
+            }
+            Suite::load(path)?
+        }
+        None => Suite::builtin(
+            Vec::new(),
+            if workloads.is_empty() {
+                BUILTIN_WORKLOADS.iter().map(|w| w.to_string()).collect()
+            } else {
+                workloads
+            },
+        ),
+    };
+    if !backends.is_empty() {
+        suite.backends = backends;
+    } else if suite.backends.is_empty() {
+        suite.backends = BACKENDS
+            .iter()
+            .filter(|b| **b != "rpc" || suite.rpc.is_some())
+            .map(|b| b.to_string())
+            .collect();
+    }
+    suite.sample_size = sample_size.or(suite.sample_size);
+    suite.measurement_time = measurement_time.or(suite.measurement_time);
+    suite.warm_up_time = warm_up_time.or(suite.warm_up_time);
+    suite.reset = reset.or(suite.reset);
+    if let Some(state) = state {
+        // relative to where we're run from, not the suite
+        suite.state = Some(std::env::current_dir()?.join(state));
+    }
+    if latency.is_some() || jitter.is_some() || bandwidth.is_some() || drop_rate.is_some() {
+        let network = suite.network.unwrap_or_default();
+        suite.network = Some(NetworkConditions {
+            latency_ms: latency.unwrap_or(network.latency_ms),
+            jitter_ms: jitter.unwrap_or(network.jitter_ms),
+            bandwidth_kbps: bandwidth.or(network.bandwidth_kbps),
+            drop_rate: drop_rate.unwrap_or(network.drop_rate),
+        });
+    }
+    suite.validate()?;
+
+    if (!concurrency.is_empty()
+        || rate.is_some()
+        || phases
+        || calls
+        || layers
+        || !state_sizes.is_empty()
+        || !loops.is_empty()
+        || !depths.is_empty())
+        && (save_baseline.is_some() || check_baseline.is_some() || speedup_over.is_some())
+    {
+        bail!(
+            "baselines and speedups aren't supported with --concurrency, --rate, --phases, \
+             --calls, --layers, --state-sizes, --loops or --depths"
+        );
+    }
+    if calls {
+        let settings = CallSettings {
+            iterations: suite
+                .sample_size
+                .map_or(CallSettings::default().iterations, |n| n as u64),
+        };
+        settings.validate()?;
+        let report = CallReport::new(suite.calls(&settings).await?);
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if phases {
+        let settings = PhaseSettings {
+            transactions: suite
+                .sample_size
+                .unwrap_or(PhaseSettings::default().transactions),
+            ..Default::default()
+        };
+        settings.validate()?;
+        let report = PhaseReport::new(suite.phases(&settings).await?);
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if let Some(rate) = rate {
+        let settings = OpenLoopSettings {
+            rate,
+            duration: suite
+                .measurement_time
+                .unwrap_or(OpenLoopSettings::default().duration),
+        };
+        settings.validate()?;
+        let report = OpenLoopReport::new(suite.open_loop(&settings).await?);
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if !concurrency.is_empty() {
+        let defaults = ThroughputSettings::default();
+        let settings = ThroughputSettings {
+            concurrency,
+            duration: suite.measurement_time.unwrap_or(defaults.duration),
+            warm_up: suite.warm_up_time.unwrap_or(defaults.warm_up),
+        };
+        settings.validate()?;
+        let report = ThroughputReport::new(suite.throughput(&settings).await?);
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if !state_sizes.is_empty() {
+        let settings = ScalingSettings { sizes: state_sizes };
+        settings.validate()?;
+        let mut c = Criterion::default().output_directory(&criterion_dir);
+        let report = ScalingReport::new(suite.scaling(&mut c, &settings).await?);
+        c.final_summary();
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if !loops.is_empty() {
+        let settings = ComputeSettings { loops };
+        settings.validate()?;
+        let mut c = Criterion::default().output_directory(&criterion_dir);
+        let report = ComputeReport::new(suite.compute(&mut c, &settings).await?);
+        c.final_summary();
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if !depths.is_empty() {
+        let settings = DepthSettings { depths };
+        settings.validate()?;
+        let mut c = Criterion::default().output_directory(&criterion_dir);
+        let report = DepthReport::new(suite.depth(&mut c, &settings).await?);
+        c.final_summary();
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+    if layers {
+        let mut c = Criterion::default().output_directory(&criterion_dir);
+        let report = StackReport::new(suite.stack(&mut c).await?);
+        c.final_summary();
+        print!("{}", report.to_markdown());
+        for output in outputs {
+            report.save(&output)?;
+            println!("Wrote {}", output.display());
+        }
+        return Ok(());
+    }
+
+    let speedup_over = match speedup_over {
+        Some(key) => Some(
+            suite
+                .backend_name(&key)
+                .with_context(|| format!("invalid --speedup-over {}", key))?,
+        ),
+        None => None,
+    };
+
+    // fail on a missing baseline before spending time benchmarking
+    let previous = match &check_baseline {
+        Some(name) => Some(Report::load(baseline_path(&baseline_dir, name))?),
+        None => None,
+    };
+
+    let mut c = Criterion::default().output_directory(&criterion_dir);
+    let report = Report::new(suite.run(&mut c).await?);
+    c.final_summary();
+    let mismatches = report.gas_mismatches();
+    if !mismatches.is_empty() {
+        eprintln!("Gas differs between backends for {}", mismatches.join(", "));
+    }
+    if let Some(name) = &save_baseline {
+        let path = baseline_path(&baseline_dir, name);
+        report.save(&path)?;
+        println!("Saved baseline {} to {}", name, path.display());
+    }
+    for output in outputs {
+        report.save(&output)?;
+        println!("Wrote {}", output.display());
+    }
+    if let Some(baseline) = speedup_over {
+        let comparison = Comparison::new(&report, baseline, CompareOptions::default())?;
+        print!("{}", comparison.to_markdown());
+    }
+    if let (Some(name), Some(previous)) = (check_baseline, previous) {
+        let gate = Gate::new(&previous, &report, threshold);
+        print!("{}", gate.to_markdown());
+        for label in &gate.unmatched {
+            println!("{} isn't in baseline {}, skipped", label, name);
+        }
+        if !gate.passed() {
+            eprintln!("Regressed past {} against baseline {}:", threshold, name);
+            for check in gate.regressions() {
+                eprintln!("  {} ({:+.2}%)", check.label, check.change);
+            }
+            for label in &gate.missing {
+                eprintln!("  {} (in the baseline but not this run)", label);
+            }
+            std::process::exit(REGRESSION_EXIT_CODE);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the run saved as baseline `name` in `baseline_dir`, or the results
+/// file at `name` when there is one.
+fn load_saved(baseline_dir: &Path, name: &str) -> Result<Report> {
+    let path = Path::new(name);
+    if path.is_file() {
+        Report::load(path)
+    } else {
+        Report::load(baseline_path(baseline_dir, name))
+    }
+}
@@ -0,0 +1,534 @@
+pub use compute::*;
+/// This module was auto-generated with ethers-rs Abigen.
+/// More information at: <https://github.com/gakonst/ethers-rs>
+#[allow(
+    clippy::enum_variant_names,
+    clippy::too_many_arguments,
+    clippy::upper_case_acronyms,
+    clippy::type_complexity,
+    dead_code,
+    non_camel_case_types
+)]
+pub mod compute {
+    #[allow(deprecated)]
+    fn __abi() -> ::ethers::core::abi::Abi {
+        ::ethers::core::abi::ethabi::Contract {
+            constructor: ::core::option::Option::None,
+            functions: ::core::convert::From::from([
+                (
+                    ::std::borrow::ToOwned::to_owned("arithmetic"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("arithmetic"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("n"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("x"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::Pure,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("expandMemory"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("expandMemory"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("n"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::Pure,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("hashing"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("hashing"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("n"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("h"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::FixedBytes(32usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("bytes32"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::Pure,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("rounds"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("rounds"),
+                        inputs: ::std::vec![],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("store"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("store"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("n"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("values"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("values"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                    },],
+                ),
+            ]),
+            events: ::std::collections::BTreeMap::new(),
+            errors: ::std::collections::BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        }
+    }
+    ///The parsed JSON ABI of the contract.
+    pub static COMPUTE_ABI: ::ethers::contract::Lazy<::ethers::core::abi::Abi> =
+        ::ethers::contract::Lazy::new(__abi);
+    #[rustfmt::skip]
+    const __BYTECODE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Compute.bin"));
+    /// The bytecode of the contract.
+    pub static COMPUTE_BYTECODE: ::ethers::core::types::Bytes =
+        ::ethers::core::types::Bytes::from_static(__BYTECODE);
+    #[rustfmt::skip]
+    const __DEPLOYED_BYTECODE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Compute.bin-runtime"));
+    /// The deployed bytecode of the contract.
+    pub static COMPUTE_DEPLOYED_BYTECODE: ::ethers::core::types::Bytes =
+        ::ethers::core::types::Bytes::from_static(__DEPLOYED_BYTECODE);
+    pub struct Compute<M>(::ethers::contract::Contract<M>);
+    impl<M> ::core::clone::Clone for Compute<M> {
+        fn clone(&self) -> Self {
+            Self(::core::clone::Clone::clone(&self.0))
+        }
+    }
+    impl<M> ::core::ops::Deref for Compute<M> {
+        type Target = ::ethers::contract::Contract<M>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+    impl<M> ::core::ops::DerefMut for Compute<M> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+    impl<M> ::core::fmt::Debug for Compute<M> {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            f.debug_tuple(::core::stringify!(Compute))
+                .field(&self.address())
+                .finish()
+        }
+    }
+    impl<M: ::ethers::providers::Middleware> Compute<M> {
+        /// Creates a new contract instance with the specified `ethers` client at
+        /// `address`. The contract derefs to a `ethers::Contract` object.
+        pub fn new<T: Into<::ethers::core::types::Address>>(
+            address: T,
+            client: ::std::sync::Arc<M>,
+        ) -> Self {
+            Self(::ethers::contract::Contract::new(
+                address.into(),
+                COMPUTE_ABI.clone(),
+                client,
+            ))
+        }
+        /// Constructs the general purpose `Deployer` instance based on the provided constructor arguments and sends it.
+        /// Returns a new instance of a deployer that returns an instance of this contract after sending the transaction
+        ///
+        /// Notes:
+        /// - If there are no constructor arguments, you should pass `()` as the argument.
+        /// - The default poll duration is 7 seconds.
+        /// - The default number of confirmations is 1 block.
+        ///
+        ///
+        /// # Example
+        ///
+        /// Generate contract bindings with `abigen!` and deploy a new contract instance.
+        ///
+        /// *Note*: this requires a `bytecode` and `abi` object in the `greeter.json` artifact.
+        ///
+        /// ```ignore
+        /// # async fn deploy<M: ethers::providers::Middleware>(client: ::std::sync::Arc<M>) {
+        ///     abigen!(Greeter, "../greeter.json");
+        ///
+        ///    let greeter_contract = Greeter::deploy(client, "Hello world!".to_string()).unwrap().send().await.unwrap();
+        ///    let msg = greeter_contract.greet().call().await.unwrap();
+        /// # }
+        /// ```
+        pub fn deploy<T: ::ethers::core::abi::Tokenize>(
+            client: ::std::sync::Arc<M>,
+            constructor_args: T,
+        ) -> ::core::result::Result<
+            ::ethers::contract::builders::ContractDeployer<M, Self>,
+            ::ethers::contract::ContractError<M>,
+        > {
+            let factory = ::ethers::contract::ContractFactory::new(
+                COMPUTE_ABI.clone(),
+                COMPUTE_BYTECODE.clone().into(),
+                client,
+            );
+            let deployer = factory.deploy(constructor_args)?;
+            let deployer = ::ethers::contract::ContractDeployer::new(deployer);
+            Ok(deployer)
+        }
+        ///Calls the contract's `arithmetic` (0x9381a585) function
+        pub fn arithmetic(
+            &self,
+            n: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([147, 129, 165, 133], n)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `expandMemory` (0xf96ef556) function
+        pub fn expand_memory(
+            &self,
+            n: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([249, 110, 245, 86], n)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `hashing` (0x89e152ca) function
+        pub fn hashing(
+            &self,
+            n: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, [u8; 32]> {
+            self.0
+                .method_hash([137, 225, 82, 202], n)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `rounds` (0xa2e800ad) function
+        pub fn rounds(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([162, 232, 0, 173], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `store` (0x6057361d) function
+        pub fn store(
+            &self,
+            n: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
+            self.0
+                .method_hash([96, 87, 54, 29], n)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `values` (0x5e383d21) function
+        pub fn values(
+            &self,
+            p0: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([94, 56, 61, 33], p0)
+                .expect("method not found (this should never happen)")
+        }
+    }
+    impl<M: ::ethers::providers::Middleware> From<::ethers::contract::Contract<M>> for Compute<M> {
+        fn from(contract: ::ethers::contract::Contract<M>) -> Self {
+            Self::new(contract.address(), contract.client())
+        }
+    }
+    ///Container type for all input parameters for the `arithmetic` function with signature `arithmetic(uint256)` and selector `0x9381a585`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "arithmetic", abi = "arithmetic(uint256)")]
+    pub struct ArithmeticCall {
+        pub n: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `expandMemory` function with signature `expandMemory(uint256)` and selector `0xf96ef556`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "expandMemory", abi = "expandMemory(uint256)")]
+    pub struct ExpandMemoryCall {
+        pub n: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `hashing` function with signature `hashing(uint256)` and selector `0x89e152ca`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "hashing", abi = "hashing(uint256)")]
+    pub struct HashingCall {
+        pub n: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `rounds` function with signature `rounds()` and selector `0xa2e800ad`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "rounds", abi = "rounds()")]
+    pub struct RoundsCall;
+    ///Container type for all input parameters for the `store` function with signature `store(uint256)` and selector `0x6057361d`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "store", abi = "store(uint256)")]
+    pub struct StoreCall {
+        pub n: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `values` function with signature `values(uint256)` and selector `0x5e383d21`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "values", abi = "values(uint256)")]
+    pub struct ValuesCall(pub ::ethers::core::types::U256);
+    ///Container type for all of the contract's call
+    #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
+    pub enum ComputeCalls {
+        Arithmetic(ArithmeticCall),
+        ExpandMemory(ExpandMemoryCall),
+        Hashing(HashingCall),
+        Rounds(RoundsCall),
+        Store(StoreCall),
+        Values(ValuesCall),
+    }
+    impl ::ethers::core::abi::AbiDecode for ComputeCalls {
+        fn decode(
+            data: impl AsRef<[u8]>,
+        ) -> ::core::result::Result<Self, ::ethers::core::abi::AbiError> {
+            let data = data.as_ref();
+            if let Ok(decoded) = <ArithmeticCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Arithmetic(decoded));
+            }
+            if let Ok(decoded) = <ExpandMemoryCall as ::ethers::core::abi::AbiDecode>::decode(data)
+            {
+                return Ok(Self::ExpandMemory(decoded));
+            }
+            if let Ok(decoded) = <HashingCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Hashing(decoded));
+            }
+            if let Ok(decoded) = <RoundsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Rounds(decoded));
+            }
+            if let Ok(decoded) = <StoreCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Store(decoded));
+            }
+            if let Ok(decoded) = <ValuesCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Values(decoded));
+            }
+            Err(::ethers::core::abi::Error::InvalidData.into())
+        }
+    }
+    impl ::ethers::core::abi::AbiEncode for ComputeCalls {
+        fn encode(self) -> Vec<u8> {
+            match self {
+                Self::Arithmetic(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::ExpandMemory(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Hashing(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Rounds(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Store(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Values(element) => ::ethers::core::abi::AbiEncode::encode(element),
+            }
+        }
+    }
+    impl ::core::fmt::Display for ComputeCalls {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            match self {
+                Self::Arithmetic(element) => ::core::fmt::Display::fmt(element, f),
+                Self::ExpandMemory(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Hashing(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Rounds(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Store(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Values(element) => ::core::fmt::Display::fmt(element, f),
+            }
+        }
+    }
+    impl ::core::convert::From<ArithmeticCall> for ComputeCalls {
+        fn from(value: ArithmeticCall) -> Self {
+            Self::Arithmetic(value)
+        }
+    }
+    impl ::core::convert::From<ExpandMemoryCall> for ComputeCalls {
+        fn from(value: ExpandMemoryCall) -> Self {
+            Self::ExpandMemory(value)
+        }
+    }
+    impl ::core::convert::From<HashingCall> for ComputeCalls {
+        fn from(value: HashingCall) -> Self {
+            Self::Hashing(value)
+        }
+    }
+    impl ::core::convert::From<RoundsCall> for ComputeCalls {
+        fn from(value: RoundsCall) -> Self {
+            Self::Rounds(value)
+        }
+    }
+    impl ::core::convert::From<StoreCall> for ComputeCalls {
+        fn from(value: StoreCall) -> Self {
+            Self::Store(value)
+        }
+    }
+    impl ::core::convert::From<ValuesCall> for ComputeCalls {
+        fn from(value: ValuesCall) -> Self {
+            Self::Values(value)
+        }
+    }
+    ///Container type for all return fields from the `arithmetic` function with signature `arithmetic(uint256)` and selector `0x9381a585`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct ArithmeticReturn {
+        pub x: ::ethers::core::types::U256,
+    }
+    ///Container type for all return fields from the `expandMemory` function with signature `expandMemory(uint256)` and selector `0xf96ef556`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct ExpandMemoryReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `hashing` function with signature `hashing(uint256)` and selector `0x89e152ca`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct HashingReturn {
+        pub h: [u8; 32],
+    }
+    ///Container type for all return fields from the `rounds` function with signature `rounds()` and selector `0xa2e800ad`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct RoundsReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `values` function with signature `values(uint256)` and selector `0x5e383d21`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct ValuesReturn(pub ::ethers::core::types::U256);
+}
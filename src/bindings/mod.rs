@@ -3,4 +3,7 @@
 //! This is autogenerated code.
 //! Do not manually edit these files.
 //! These files may be overwritten by the codegen system at any time.
+pub mod compute;
 pub mod counter;
+pub mod precompiles;
+pub mod relay;
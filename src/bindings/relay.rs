@@ -0,0 +1,483 @@
+pub use relay::*;
+/// This module was auto-generated with ethers-rs Abigen.
+/// More information at: <https://github.com/gakonst/ethers-rs>
+#[allow(
+    clippy::enum_variant_names,
+    clippy::too_many_arguments,
+    clippy::upper_case_acronyms,
+    clippy::type_complexity,
+    dead_code,
+    non_camel_case_types
+)]
+pub mod relay {
+    #[allow(deprecated)]
+    fn __abi() -> ::ethers::core::abi::Abi {
+        ::ethers::core::abi::ethabi::Contract {
+            constructor: ::core::option::Option::Some(::ethers::core::abi::ethabi::Constructor {
+                inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                    name: ::std::borrow::ToOwned::to_owned("next_"),
+                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                    internal_type: ::core::option::Option::Some(::std::borrow::ToOwned::to_owned(
+                        "contract Relay"
+                    ),),
+                },],
+            }),
+            functions: ::core::convert::From::from([
+                (
+                    ::std::borrow::ToOwned::to_owned("hits"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("hits"),
+                        inputs: ::std::vec![],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("next"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("next"),
+                        inputs: ::std::vec![],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("contract Relay"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("relayCall"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("relayCall"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("depth"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("relayDelegatecall"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("relayDelegatecall"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("depth"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                    },],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("relayStaticcall"),
+                    ::std::vec![::ethers::core::abi::ethabi::Function {
+                        name: ::std::borrow::ToOwned::to_owned("relayStaticcall"),
+                        inputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::borrow::ToOwned::to_owned("depth"),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        outputs: ::std::vec![::ethers::core::abi::ethabi::Param {
+                            name: ::std::string::String::new(),
+                            kind: ::ethers::core::abi::ethabi::ParamType::Uint(256usize,),
+                            internal_type: ::core::option::Option::Some(
+                                ::std::borrow::ToOwned::to_owned("uint256"),
+                            ),
+                        },],
+                        constant: ::core::option::Option::None,
+                        state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                    },],
+                ),
+            ]),
+            events: ::std::collections::BTreeMap::new(),
+            errors: ::std::collections::BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        }
+    }
+    ///The parsed JSON ABI of the contract.
+    pub static RELAY_ABI: ::ethers::contract::Lazy<::ethers::core::abi::Abi> =
+        ::ethers::contract::Lazy::new(__abi);
+    #[rustfmt::skip]
+    const __BYTECODE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Relay.bin"));
+    /// The bytecode of the contract.
+    pub static RELAY_BYTECODE: ::ethers::core::types::Bytes =
+        ::ethers::core::types::Bytes::from_static(__BYTECODE);
+    #[rustfmt::skip]
+    const __DEPLOYED_BYTECODE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Relay.bin-runtime"));
+    /// The deployed bytecode of the contract.
+    pub static RELAY_DEPLOYED_BYTECODE: ::ethers::core::types::Bytes =
+        ::ethers::core::types::Bytes::from_static(__DEPLOYED_BYTECODE);
+    pub struct Relay<M>(::ethers::contract::Contract<M>);
+    impl<M> ::core::clone::Clone for Relay<M> {
+        fn clone(&self) -> Self {
+            Self(::core::clone::Clone::clone(&self.0))
+        }
+    }
+    impl<M> ::core::ops::Deref for Relay<M> {
+        type Target = ::ethers::contract::Contract<M>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+    impl<M> ::core::ops::DerefMut for Relay<M> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+    impl<M> ::core::fmt::Debug for Relay<M> {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            f.debug_tuple(::core::stringify!(Relay))
+                .field(&self.address())
+                .finish()
+        }
+    }
+    impl<M: ::ethers::providers::Middleware> Relay<M> {
+        /// Creates a new contract instance with the specified `ethers` client at
+        /// `address`. The contract derefs to a `ethers::Contract` object.
+        pub fn new<T: Into<::ethers::core::types::Address>>(
+            address: T,
+            client: ::std::sync::Arc<M>,
+        ) -> Self {
+            Self(::ethers::contract::Contract::new(
+                address.into(),
+                RELAY_ABI.clone(),
+                client,
+            ))
+        }
+        /// Constructs the general purpose `Deployer` instance based on the provided constructor arguments and sends it.
+        /// Returns a new instance of a deployer that returns an instance of this contract after sending the transaction
+        ///
+        /// Notes:
+        /// - If there are no constructor arguments, you should pass `()` as the argument.
+        /// - The default poll duration is 7 seconds.
+        /// - The default number of confirmations is 1 block.
+        ///
+        ///
+        /// # Example
+        ///
+        /// Generate contract bindings with `abigen!` and deploy a new contract instance.
+        ///
+        /// *Note*: this requires a `bytecode` and `abi` object in the `greeter.json` artifact.
+        ///
+        /// ```ignore
+        /// # async fn deploy<M: ethers::providers::Middleware>(client: ::std::sync::Arc<M>) {
+        ///     abigen!(Greeter, "../greeter.json");
+        ///
+        ///    let greeter_contract = Greeter::deploy(client, "Hello world!".to_string()).unwrap().send().await.unwrap();
+        ///    let msg = greeter_contract.greet().call().await.unwrap();
+        /// # }
+        /// ```
+        pub fn deploy<T: ::ethers::core::abi::Tokenize>(
+            client: ::std::sync::Arc<M>,
+            constructor_args: T,
+        ) -> ::core::result::Result<
+            ::ethers::contract::builders::ContractDeployer<M, Self>,
+            ::ethers::contract::ContractError<M>,
+        > {
+            let factory = ::ethers::contract::ContractFactory::new(
+                RELAY_ABI.clone(),
+                RELAY_BYTECODE.clone().into(),
+                client,
+            );
+            let deployer = factory.deploy(constructor_args)?;
+            let deployer = ::ethers::contract::ContractDeployer::new(deployer);
+            Ok(deployer)
+        }
+        ///Calls the contract's `hits` (0xcf2470f6) function
+        pub fn hits(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([207, 36, 112, 246], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `next` (0x4c8fe526) function
+        pub fn next(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::Address> {
+            self.0
+                .method_hash([76, 143, 229, 38], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `relayCall` (0x255c10b1) function
+        pub fn relay_call(
+            &self,
+            depth: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([37, 92, 16, 177], depth)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `relayDelegatecall` (0xa71442ab) function
+        pub fn relay_delegatecall(
+            &self,
+            depth: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([167, 20, 66, 171], depth)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `relayStaticcall` (0x9f78b8e0) function
+        pub fn relay_staticcall(
+            &self,
+            depth: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([159, 120, 184, 224], depth)
+                .expect("method not found (this should never happen)")
+        }
+    }
+    impl<M: ::ethers::providers::Middleware> From<::ethers::contract::Contract<M>> for Relay<M> {
+        fn from(contract: ::ethers::contract::Contract<M>) -> Self {
+            Self::new(contract.address(), contract.client())
+        }
+    }
+    ///Container type for all input parameters for the `hits` function with signature `hits()` and selector `0xcf2470f6`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "hits", abi = "hits()")]
+    pub struct HitsCall;
+    ///Container type for all input parameters for the `next` function with signature `next()` and selector `0x4c8fe526`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "next", abi = "next()")]
+    pub struct NextCall;
+    ///Container type for all input parameters for the `relayCall` function with signature `relayCall(uint256)` and selector `0x255c10b1`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "relayCall", abi = "relayCall(uint256)")]
+    pub struct RelayCallCall {
+        pub depth: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `relayDelegatecall` function with signature `relayDelegatecall(uint256)` and selector `0xa71442ab`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "relayDelegatecall", abi = "relayDelegatecall(uint256)")]
+    pub struct RelayDelegatecallCall {
+        pub depth: ::ethers::core::types::U256,
+    }
+    ///Container type for all input parameters for the `relayStaticcall` function with signature `relayStaticcall(uint256)` and selector `0x9f78b8e0`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    #[ethcall(name = "relayStaticcall", abi = "relayStaticcall(uint256)")]
+    pub struct RelayStaticcallCall {
+        pub depth: ::ethers::core::types::U256,
+    }
+    ///Container type for all of the contract's call
+    #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
+    pub enum RelayCalls {
+        Hits(HitsCall),
+        Next(NextCall),
+        RelayCall(RelayCallCall),
+        RelayDelegatecall(RelayDelegatecallCall),
+        RelayStaticcall(RelayStaticcallCall),
+    }
+    impl ::ethers::core::abi::AbiDecode for RelayCalls {
+        fn decode(
+            data: impl AsRef<[u8]>,
+        ) -> ::core::result::Result<Self, ::ethers::core::abi::AbiError> {
+            let data = data.as_ref();
+            if let Ok(decoded) = <HitsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Hits(decoded));
+            }
+            if let Ok(decoded) = <NextCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Next(decoded));
+            }
+            if let Ok(decoded) = <RelayCallCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::RelayCall(decoded));
+            }
+            if let Ok(decoded) =
+                <RelayDelegatecallCall as ::ethers::core::abi::AbiDecode>::decode(data)
+            {
+                return Ok(Self::RelayDelegatecall(decoded));
+            }
+            if let Ok(decoded) =
+                <RelayStaticcallCall as ::ethers::core::abi::AbiDecode>::decode(data)
+            {
+                return Ok(Self::RelayStaticcall(decoded));
+            }
+            Err(::ethers::core::abi::Error::InvalidData.into())
+        }
+    }
+    impl ::ethers::core::abi::AbiEncode for RelayCalls {
+        fn encode(self) -> Vec<u8> {
+            match self {
+                Self::Hits(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Next(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::RelayCall(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::RelayDelegatecall(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::RelayStaticcall(element) => ::ethers::core::abi::AbiEncode::encode(element),
+            }
+        }
+    }
+    impl ::core::fmt::Display for RelayCalls {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            match self {
+                Self::Hits(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Next(element) => ::core::fmt::Display::fmt(element, f),
+                Self::RelayCall(element) => ::core::fmt::Display::fmt(element, f),
+                Self::RelayDelegatecall(element) => ::core::fmt::Display::fmt(element, f),
+                Self::RelayStaticcall(element) => ::core::fmt::Display::fmt(element, f),
+            }
+        }
+    }
+    impl ::core::convert::From<HitsCall> for RelayCalls {
+        fn from(value: HitsCall) -> Self {
+            Self::Hits(value)
+        }
+    }
+    impl ::core::convert::From<NextCall> for RelayCalls {
+        fn from(value: NextCall) -> Self {
+            Self::Next(value)
+        }
+    }
+    impl ::core::convert::From<RelayCallCall> for RelayCalls {
+        fn from(value: RelayCallCall) -> Self {
+            Self::RelayCall(value)
+        }
+    }
+    impl ::core::convert::From<RelayDelegatecallCall> for RelayCalls {
+        fn from(value: RelayDelegatecallCall) -> Self {
+            Self::RelayDelegatecall(value)
+        }
+    }
+    impl ::core::convert::From<RelayStaticcallCall> for RelayCalls {
+        fn from(value: RelayStaticcallCall) -> Self {
+            Self::RelayStaticcall(value)
+        }
+    }
+    ///Container type for all return fields from the `hits` function with signature `hits()` and selector `0xcf2470f6`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct HitsReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `next` function with signature `next()` and selector `0x4c8fe526`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct NextReturn(pub ::ethers::core::types::Address);
+    ///Container type for all return fields from the `relayCall` function with signature `relayCall(uint256)` and selector `0x255c10b1`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct RelayCallReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `relayDelegatecall` function with signature `relayDelegatecall(uint256)` and selector `0xa71442ab`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct RelayDelegatecallReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `relayStaticcall` function with signature `relayStaticcall(uint256)` and selector `0x9f78b8e0`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+    )]
+    pub struct RelayStaticcallReturn(pub ::ethers::core::types::U256);
+}
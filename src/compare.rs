@@ -0,0 +1,233 @@
+//! Comparing backends against each other.
+//!
+//! For every workload the other backends are compared against a baseline
+//! backend by their speedup, the baseline's mean time over theirs. The
+//! confidence interval comes from bootstrapping the per-iteration times of
+//! both, and a backend only counts as faster or slower when the interval
+//! excludes 1.
+
+use anyhow::{bail, Ok, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{self, Write};
+
+use crate::{
+    report::{format_ns, Report},
+    results::{percentile, Sample, WorkloadResult},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompareOptions {
+    /// Bootstrap resamples per comparison.
+    pub resamples: usize,
+    /// Confidence level of the interval, e.g. 0.95.
+    pub confidence: f64,
+    /// Seeds the bootstrap so that a comparison is reproducible.
+    pub seed: u64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            resamples: 10_000,
+            confidence: 0.95,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Verdict {
+    Faster,
+    Slower,
+    /// The confidence interval includes a speedup of 1.
+    Indistinguishable,
+    /// One side has fewer than two samples, so there is no interval.
+    TooFewSamples,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Faster => "faster",
+            Verdict::Slower => "slower",
+            Verdict::Indistinguishable => "no significant difference",
+            Verdict::TooFewSamples => "too few samples",
+        })
+    }
+}
+
+/// One backend against the baseline on one workload.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Speedup {
+    pub workload: String,
+    pub backend: String,
+    /// Mean time per iteration of the baseline, in nanoseconds.
+    pub baseline_mean: f64,
+    /// Mean time per iteration of the backend, in nanoseconds.
+    pub mean: f64,
+    /// Mean time of the baseline over the backend's, above 1 when the
+    /// backend is faster.
+    pub speedup: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub verdict: Verdict,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    /// The backend everything is compared against.
+    pub baseline: String,
+    pub options: CompareOptions,
+    pub speedups: Vec<Speedup>,
+}
+
+impl Comparison {
+    /// Compares every other backend in `report` against `baseline`, skipping
+    /// workloads that weren't run on both. Backend names are matched ignoring
+    /// case, so "anvil" finds the "Anvil" results.
+    pub fn new(report: &Report, baseline: &str, options: CompareOptions) -> Result<Self> {
+        if !(options.confidence > 0.0 && options.confidence < 1.0) {
+            bail!("confidence must be between 0 and 1");
+        }
+        if options.resamples == 0 {
+            bail!("need at least one bootstrap resample");
+        }
+        let base = match report
+            .results
+            .iter()
+            .find(|results| results.backend.name.eq_ignore_ascii_case(baseline))
+        {
+            Some(base) => base,
+            None => bail!(
+                "no results for baseline backend {:?}, have {:?}",
+                baseline,
+                report.backends()
+            ),
+        };
+        let mut rng = StdRng::seed_from_u64(options.seed);
+        let mut speedups = Vec::new();
+        for workload in &base.workloads {
+            for other in report
+                .results
+                .iter()
+                .filter(|results| results.backend.name != base.backend.name)
+            {
+                if let Some(result) = other.get(&workload.workload) {
+                    speedups.push(speedup(
+                        workload,
+                        result,
+                        &other.backend.name,
+                        &options,
+                        &mut rng,
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            baseline: base.backend.name.clone(),
+            options,
+            speedups,
+        })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "| Workload | Backend | Mean | {} mean | Speedup | {:.0}% CI | Verdict |\n",
+            self.baseline,
+            self.options.confidence * 100.0
+        );
+        md.push_str("| --- | --- | ---: | ---: | ---: | ---: | --- |\n");
+        for speedup in &self.speedups {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | {:.2}x | {:.2}x – {:.2}x | {} |",
+                speedup.workload,
+                speedup.backend,
+                format_ns(speedup.mean),
+                format_ns(speedup.baseline_mean),
+                speedup.speedup,
+                speedup.lower,
+                speedup.upper,
+                speedup.verdict
+            )
+            .unwrap();
+        }
+        md
+    }
+}
+
+fn speedup(
+    base: &WorkloadResult,
+    other: &WorkloadResult,
+    backend: &str,
+    options: &CompareOptions,
+    rng: &mut StdRng,
+) -> Speedup {
+    let base_times = per_iteration(&base.samples);
+    let other_times = per_iteration(&other.samples);
+    let (baseline_mean, other_mean) = (mean(&base_times), mean(&other_times));
+    let ratio = baseline_mean / other_mean;
+    let (lower, upper, verdict) = if base_times.len() < 2 || other_times.len() < 2 {
+        (ratio, ratio, Verdict::TooFewSamples)
+    } else {
+        let (lower, upper) = bootstrap_ratio(
+            &base_times,
+            &other_times,
+            options.resamples,
+            options.confidence,
+            rng,
+        );
+        let verdict = if lower > 1.0 {
+            Verdict::Faster
+        } else if upper < 1.0 {
+            Verdict::Slower
+        } else {
+            Verdict::Indistinguishable
+        };
+        (lower, upper, verdict)
+    };
+    Speedup {
+        workload: base.workload.clone(),
+        backend: backend.to_string(),
+        baseline_mean,
+        mean: other_mean,
+        speedup: ratio,
+        lower,
+        upper,
+        verdict,
+    }
+}
+
+/// A bootstrapped confidence interval for the ratio of the mean of
+/// `numerator` to the mean of `denominator`.
+pub(crate) fn bootstrap_ratio(
+    numerator: &[f64],
+    denominator: &[f64],
+    resamples: usize,
+    confidence: f64,
+    rng: &mut StdRng,
+) -> (f64, f64) {
+    let mut ratios: Vec<f64> = (0..resamples)
+        .map(|_| resampled_mean(numerator, rng) / resampled_mean(denominator, rng))
+        .collect();
+    ratios.sort_by(f64::total_cmp);
+    let tail = (1.0 - confidence) / 2.0 * 100.0;
+    (percentile(&ratios, tail), percentile(&ratios, 100.0 - tail))
+}
+
+pub(crate) fn per_iteration(samples: &[Sample]) -> Vec<f64> {
+    samples.iter().map(Sample::per_iteration_ns).collect()
+}
+
+pub(crate) fn mean(times: &[f64]) -> f64 {
+    times.iter().sum::<f64>() / times.len() as f64
+}
+
+fn resampled_mean(times: &[f64], rng: &mut StdRng) -> f64 {
+    (0..times.len())
+        .map(|_| times[rng.gen_range(0..times.len())])
+        .sum::<f64>()
+        / times.len() as f64
+}
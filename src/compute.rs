@@ -0,0 +1,280 @@
+//! How much of a call is spent executing it.
+//!
+//! `Stateless Call` runs `ArbiterMath::cdf` once, which is too little work to
+//! stand out from the cost of making a call at all. The `Compute` contract in
+//! `contracts/compute.sol` instead loops over one kind of EVM work `n` times:
+//! arithmetic, keccak hashing, memory expansion or storage writes. Each is
+//! benchmarked with Criterion at every loop count, and a line fitted through
+//! the mean times splits them into a fixed cost per call, where it crosses
+//! zero loops, and a cost per loop, its slope.
+
+use anyhow::{bail, Ok, Result};
+use criterion::{BenchmarkId, Criterion};
+use ethers::{providers::Middleware, types::U256};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, path::Path, sync::Arc, time::Instant};
+
+use crate::{
+    bench_functions::gas_used,
+    bindings::compute::{Compute, COMPUTE_BYTECODE},
+    record,
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, WorkloadResult},
+    utils,
+    workload::BenchSettings,
+};
+
+/// The kinds of work looped over, by the name they're benchmarked under.
+pub const COMPUTE_WORKLOADS: &[&str] = &["Arithmetic", "Hashing", "Memory", "Storage"];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComputeSettings {
+    /// Loop counts to benchmark at, in increasing order.
+    pub loops: Vec<u64>,
+}
+
+impl Default for ComputeSettings {
+    fn default() -> Self {
+        Self {
+            loops: vec![1, 10, 100, 1_000],
+        }
+    }
+}
+
+impl ComputeSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.loops.len() < 2 {
+            bail!("at least two loop counts are needed to fit a line through");
+        }
+        if self.loops.windows(2).any(|pair| pair[0] >= pair[1]) {
+            bail!("loop counts must be increasing");
+        }
+        Ok(())
+    }
+}
+
+/// One iteration of `workload`, looping `loops` times. Storage writes are
+/// sent as a transaction, the rest are calls.
+async fn compute<M: Middleware + 'static>(
+    contract: &Compute<M>,
+    workload: &str,
+    loops: u64,
+) -> Result<()> {
+    let n = U256::from(loops);
+    match workload {
+        "Arithmetic" => {
+            contract.arithmetic(n).call().await?;
+        }
+        "Hashing" => {
+            contract.hashing(n).call().await?;
+        }
+        "Memory" => {
+            contract.expand_memory(n).call().await?;
+        }
+        "Storage" => {
+            contract.store(n).send().await?.await?;
+        }
+        other => bail!("unknown compute workload {:?}", other),
+    }
+    Ok(())
+}
+
+/// The gas of one iteration of `workload`, like [`crate::Workload::gas`].
+async fn gas<M: Middleware + 'static>(
+    contract: &Compute<M>,
+    workload: &str,
+    loops: u64,
+) -> Result<Option<u64>> {
+    let n = U256::from(loops);
+    let estimate = match workload {
+        "Arithmetic" => contract.arithmetic(n).estimate_gas().await?,
+        "Hashing" => contract.hashing(n).estimate_gas().await?,
+        "Memory" => contract.expand_memory(n).estimate_gas().await?,
+        _ => return Ok(gas_used(contract.store(n).send().await?.await?)),
+    };
+    Ok(Some(estimate.as_u64()))
+}
+
+/// The workloads benchmarked at one loop count.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoopResults {
+    pub loops: u64,
+    pub workloads: Vec<WorkloadResult>,
+}
+
+/// A workload's mean time per call as a line over its loop count.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fit {
+    /// Nanoseconds a call takes whatever its loop count.
+    pub fixed_ns: f64,
+    /// Nanoseconds each loop adds.
+    pub per_loop_ns: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComputeResults {
+    pub backend: BackendInfo,
+    /// In increasing order of loop count.
+    pub loops: Vec<LoopResults>,
+}
+
+impl ComputeResults {
+    pub fn get(&self, loops: u64, workload: &str) -> Option<&WorkloadResult> {
+        self.loops
+            .iter()
+            .find(|results| results.loops == loops)?
+            .workloads
+            .iter()
+            .find(|result| result.workload == workload)
+    }
+
+    /// The least squares line through the mean times of `workload`, or
+    /// `None` if it wasn't benchmarked at two loop counts or more.
+    pub fn fit(&self, workload: &str) -> Option<Fit> {
+        let points: Vec<(f64, f64)> = self
+            .loops
+            .iter()
+            .filter_map(|results| {
+                let result = self.get(results.loops, workload)?;
+                Some((results.loops as f64, result.summary.mean))
+            })
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let per_loop_ns = covariance / variance;
+        Some(Fit {
+            fixed_ns: mean_y - per_loop_ns * mean_x,
+            per_loop_ns,
+        })
+    }
+}
+
+/// Deploys the compute contract on `client` and benchmarks each of
+/// [`COMPUTE_WORKLOADS`] at the loop counts in `settings`, under the
+/// Criterion group "`label` Compute".
+pub async fn compute_middleware<M: Middleware + 'static>(
+    c: &mut Criterion,
+    client: Arc<M>,
+    label: &str,
+    settings: &ComputeSettings,
+    bench: &BenchSettings,
+) -> Result<ComputeResults> {
+    settings.validate()?;
+    let backend = BackendInfo::query(label, &*client).await;
+    utils::ensure_compiled("Compute", &COMPUTE_BYTECODE)?;
+    let contract = Compute::deploy(client, ())?.send().await?;
+
+    let mut group = c.benchmark_group(format!("{} Compute", label));
+    bench.apply(&mut group);
+    let mut results: Vec<LoopResults> = settings
+        .loops
+        .iter()
+        .map(|&loops| LoopResults {
+            loops,
+            workloads: Vec::new(),
+        })
+        .collect();
+    for &name in COMPUTE_WORKLOADS {
+        println!("{}: benchmarking {} loops", label, name);
+        for (&loops, results) in settings.loops.iter().zip(&mut results) {
+            let id = Some(BenchmarkId::new(name, loops));
+            let contract = &contract;
+            let result = record(&mut group, id, label, name, bench, |iters| async move {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    compute(contract, name, loops).await?;
+                }
+                Ok(start.elapsed())
+            })?;
+            if let Some(mut result) = result {
+                // a backend that can't report gas leaves it unset
+                result.gas = gas(contract, name, loops).await.ok().flatten();
+                results.workloads.push(result);
+            }
+        }
+    }
+    group.finish();
+    Ok(ComputeResults {
+        backend,
+        loops: results,
+    })
+}
+
+/// The results of a loop count sweep, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComputeReport {
+    pub schema_version: u32,
+    pub results: Vec<ComputeResults>,
+}
+
+impl ComputeReport {
+    pub fn new(results: Vec<ComputeResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A row per backend and workload, with the mean time at each loop count
+    /// and the fixed and per loop costs fitted to them.
+    pub fn to_markdown(&self) -> String {
+        let Some(first) = self.results.first() else {
+            return String::new();
+        };
+        let loops: Vec<u64> = first.loops.iter().map(|results| results.loops).collect();
+        let mut md = String::from("| Benchmark |");
+        for n in &loops {
+            write!(md, " {} loops |", n).unwrap();
+        }
+        md.push_str(" Per call | Per loop |\n| --- |");
+        md.push_str(&" ---: |".repeat(loops.len() + 2));
+        md.push('\n');
+        for results in &self.results {
+            for workload in COMPUTE_WORKLOADS {
+                write!(md, "| {} {} |", results.backend.name, workload).unwrap();
+                for &n in &loops {
+                    match results.get(n, workload) {
+                        Some(result) => write!(md, " {} |", format_ns(result.summary.mean)),
+                        None => write!(md, " - |"),
+                    }
+                    .unwrap();
+                }
+                match results.fit(workload) {
+                    Some(fit) => writeln!(
+                        md,
+                        " {} | {} |",
+                        signed_ns(fit.fixed_ns),
+                        signed_ns(fit.per_loop_ns)
+                    ),
+                    None => writeln!(md, " - | - |"),
+                }
+                .unwrap();
+            }
+        }
+        md
+    }
+}
+
+/// Noise can fit a line with a negative intercept or slope.
+fn signed_ns(ns: f64) -> String {
+    if ns < 0.0 {
+        format!("-{}", format_ns(-ns))
+    } else {
+        format_ns(ns)
+    }
+}
@@ -0,0 +1,237 @@
+//! How the cost of a call grows with how deeply it nests.
+//!
+//! Every workload so far calls a single contract. Here a chain of `Relay`
+//! contracts from `contracts/relay.sol` is deployed, each link holding the
+//! address of the next, and a call into the first link goes `depth` links
+//! down with `CALL`, `DELEGATECALL` or `STATICCALL`. Each kind is benchmarked
+//! with Criterion at every depth, parameterized by it, so Criterion plots
+//! time against depth. They're sent as calls, so that only executing them
+//! is timed.
+
+use anyhow::{bail, Ok, Result};
+use criterion::{BenchmarkId, Criterion};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, path::Path, sync::Arc, time::Instant};
+
+use crate::{
+    bindings::relay::{Relay, RELAY_BYTECODE},
+    record,
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, WorkloadResult},
+    utils,
+    workload::BenchSettings,
+};
+
+/// The kinds of nested call, by the name they're benchmarked under.
+pub const DEPTH_WORKLOADS: &[&str] = &["Call", "Delegatecall", "Staticcall"];
+
+/// The EVM fails calls nested deeper than 1024 frames, the first included.
+pub const MAX_DEPTH: usize = 1023;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepthSettings {
+    /// Numbers of nested calls to benchmark at, in increasing order.
+    pub depths: Vec<usize>,
+}
+
+impl Default for DepthSettings {
+    fn default() -> Self {
+        Self {
+            depths: vec![0, 1, 2, 4, 8, 16, 32],
+        }
+    }
+}
+
+impl DepthSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.depths.is_empty() {
+            bail!("at least one depth is needed");
+        }
+        if self.depths.windows(2).any(|pair| pair[0] >= pair[1]) {
+            bail!("depths must be increasing");
+        }
+        if self.depths.iter().any(|&depth| depth > MAX_DEPTH) {
+            bail!("calls can't nest deeper than {}", MAX_DEPTH);
+        }
+        Ok(())
+    }
+}
+
+/// Deploys a chain of `links` relays, the last one first, returning the
+/// first.
+async fn deploy_chain<M: Middleware + 'static>(client: Arc<M>, links: usize) -> Result<Relay<M>> {
+    utils::ensure_compiled("Relay", &RELAY_BYTECODE)?;
+    let mut next = Address::zero();
+    let mut first = None;
+    for _ in 0..links {
+        let relay = Relay::deploy(client.clone(), next)?.send().await?;
+        next = relay.address();
+        first = Some(relay);
+    }
+    // there's always the one link called into
+    Ok(first.unwrap())
+}
+
+/// One iteration of `workload`, `depth` calls deep.
+async fn relay<M: Middleware + 'static>(
+    first: &Relay<M>,
+    workload: &str,
+    depth: usize,
+) -> Result<()> {
+    let depth = U256::from(depth);
+    match workload {
+        "Call" => first.relay_call(depth).call().await?,
+        "Delegatecall" => first.relay_delegatecall(depth).call().await?,
+        "Staticcall" => first.relay_staticcall(depth).call().await?,
+        other => bail!("unknown depth workload {:?}", other),
+    };
+    Ok(())
+}
+
+/// The gas of one iteration of `workload`, like [`crate::Workload::gas`].
+async fn gas<M: Middleware + 'static>(
+    first: &Relay<M>,
+    workload: &str,
+    depth: usize,
+) -> Result<Option<u64>> {
+    let depth = U256::from(depth);
+    let estimate = match workload {
+        "Call" => first.relay_call(depth).estimate_gas().await?,
+        "Delegatecall" => first.relay_delegatecall(depth).estimate_gas().await?,
+        _ => first.relay_staticcall(depth).estimate_gas().await?,
+    };
+    Ok(Some(estimate.as_u64()))
+}
+
+/// The workloads benchmarked at one depth.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub depth: usize,
+    pub workloads: Vec<WorkloadResult>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepthResults {
+    pub backend: BackendInfo,
+    /// In increasing order of depth.
+    pub depths: Vec<DepthLevel>,
+}
+
+impl DepthResults {
+    pub fn get(&self, depth: usize, workload: &str) -> Option<&WorkloadResult> {
+        self.depths
+            .iter()
+            .find(|level| level.depth == depth)?
+            .workloads
+            .iter()
+            .find(|result| result.workload == workload)
+    }
+}
+
+/// Deploys a chain of relays on `client` as long as the deepest of the
+/// depths in `settings` needs, and benchmarks each of [`DEPTH_WORKLOADS`] at
+/// each depth under the Criterion group "`label` Call Depth".
+pub async fn depth_middleware<M: Middleware + 'static>(
+    c: &mut Criterion,
+    client: Arc<M>,
+    label: &str,
+    settings: &DepthSettings,
+    bench: &BenchSettings,
+) -> Result<DepthResults> {
+    settings.validate()?;
+    let backend = BackendInfo::query(label, &*client).await;
+    // validated not to be empty
+    let deepest = *settings.depths.last().unwrap();
+    println!("{}: deploying {} relays", label, deepest + 1);
+    let first = deploy_chain(client, deepest + 1).await?;
+
+    let mut group = c.benchmark_group(format!("{} Call Depth", label));
+    bench.apply(&mut group);
+    let mut levels: Vec<DepthLevel> = settings
+        .depths
+        .iter()
+        .map(|&depth| DepthLevel {
+            depth,
+            workloads: Vec::new(),
+        })
+        .collect();
+    for &name in DEPTH_WORKLOADS {
+        println!("{}: benchmarking {} by depth", label, name);
+        for (&depth, level) in settings.depths.iter().zip(&mut levels) {
+            let id = Some(BenchmarkId::new(name, depth));
+            let first = &first;
+            let result = record(&mut group, id, label, name, bench, |iters| async move {
+                let start = Instant::now();
+                for _ in 0..iters {
+                    relay(first, name, depth).await?;
+                }
+                Ok(start.elapsed())
+            })?;
+            if let Some(mut result) = result {
+                // a backend that can't report gas leaves it unset
+                result.gas = gas(first, name, depth).await.ok().flatten();
+                level.workloads.push(result);
+            }
+        }
+    }
+    group.finish();
+    Ok(DepthResults {
+        backend,
+        depths: levels,
+    })
+}
+
+/// The results of a call depth sweep, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepthReport {
+    pub schema_version: u32,
+    pub results: Vec<DepthResults>,
+}
+
+impl DepthReport {
+    pub fn new(results: Vec<DepthResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A row per backend and kind of call, with the mean time at each depth.
+    pub fn to_markdown(&self) -> String {
+        let Some(first) = self.results.first() else {
+            return String::new();
+        };
+        let depths: Vec<usize> = first.depths.iter().map(|level| level.depth).collect();
+        let mut md = String::from("| Benchmark |");
+        for depth in &depths {
+            write!(md, " depth {} |", depth).unwrap();
+        }
+        md.push_str("\n| --- |");
+        md.push_str(&" ---: |".repeat(depths.len()));
+        md.push('\n');
+        for results in &self.results {
+            for workload in DEPTH_WORKLOADS {
+                write!(md, "| {} {} |", results.backend.name, workload).unwrap();
+                for &depth in &depths {
+                    match results.get(depth, workload) {
+                        Some(result) => write!(md, " {} |", format_ns(result.summary.mean)),
+                        None => write!(md, " - |"),
+                    }
+                    .unwrap();
+                }
+                md.push('\n');
+            }
+        }
+        md
+    }
+}
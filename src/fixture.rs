@@ -0,0 +1,173 @@
+//! Prebuilt chain state to benchmark against.
+//!
+//! Every backend otherwise starts from an empty genesis, which is nothing like
+//! the state real workloads run against. A fixture is built once, by
+//! [`populate`]-ing an Anvil node with funded accounts and token balances and
+//! dumping its state with `anvil_dumpState`, then saved in the JSON format of
+//! Anvil's `--dump-state`. Anvil loads it back with `--load-state`, and
+//! Arbiter through [`ChainState::to_db`].
+
+use anyhow::{bail, Context, Ok, Result};
+use arbiter_core::bindings::arbiter_token::ArbiterToken;
+use ethers::{
+    providers::Middleware,
+    types::{Address, Bytes, TransactionRequest, U256},
+    utils::keccak256,
+};
+use flate2::read::GzDecoder;
+use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{AccountInfo, Bytecode},
+};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, fs, io::Read, path::Path, sync::Arc};
+
+/// The accounts of a chain, as Anvil dumps them. Anything else in a dump,
+/// like its blocks, is ignored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChainState {
+    pub accounts: BTreeMap<Address, AccountState>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountState {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl ChainState {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw =
+            fs::read(path).with_context(|| format!("failed to read state {}", path.display()))?;
+        Self::from_bytes(&raw).with_context(|| format!("invalid state {}", path.display()))
+    }
+
+    /// Parses a state dump, gzipped as `anvil_dumpState` returns it or not.
+    pub fn from_bytes(raw: &[u8]) -> Result<Self> {
+        if raw.starts_with(&[0x1f, 0x8b]) {
+            let mut json = Vec::new();
+            GzDecoder::new(raw).read_to_end(&mut json)?;
+            return Ok(serde_json::from_slice(&json)?);
+        }
+        Ok(serde_json::from_slice(raw)?)
+    }
+
+    /// Writes the state in the format of Anvil's `--dump-state`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The state as a database for an Arbiter environment to start from.
+    pub fn to_db(&self) -> CacheDB<EmptyDB> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, account) in &self.accounts {
+            let address = address.to_fixed_bytes().into();
+            let code = (!account.code.is_empty())
+                .then(|| Bytecode::new_raw(revm::primitives::Bytes(account.code.0.clone())));
+            db.insert_account_info(
+                address,
+                AccountInfo {
+                    balance: revm_u256(account.balance),
+                    nonce: account.nonce,
+                    code,
+                    ..Default::default()
+                },
+            );
+            for (slot, value) in &account.storage {
+                // an `EmptyDB` can't fail
+                db.insert_account_storage(address, revm_u256(*slot), revm_u256(*value))
+                    .unwrap();
+            }
+        }
+        db
+    }
+}
+
+fn revm_u256(value: U256) -> revm::primitives::U256 {
+    revm::primitives::U256::from_limbs(value.0)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixtureSettings {
+    /// Accounts to fund, each holding some of every token.
+    pub accounts: usize,
+    /// Tokens to deploy.
+    pub tokens: usize,
+    /// Wei sent to each account.
+    pub balance: U256,
+}
+
+impl Default for FixtureSettings {
+    fn default() -> Self {
+        Self {
+            accounts: 1_000,
+            tokens: 3,
+            balance: U256::exp10(18),
+        }
+    }
+}
+
+impl FixtureSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.accounts == 0 {
+            bail!("a fixture needs at least one account");
+        }
+        Ok(())
+    }
+}
+
+/// The `index`th account a fixture funds, the same in every fixture.
+pub fn fixture_account(index: usize) -> Address {
+    Address::from_slice(&keccak256((index as u64).to_be_bytes())[12..])
+}
+
+/// Deploys the tokens and funds the accounts of a fixture through `client`,
+/// returning the addresses of the tokens. The sender needs enough ether to
+/// fund every account.
+pub async fn populate<M: Middleware + 'static>(
+    client: Arc<M>,
+    settings: &FixtureSettings,
+) -> Result<Vec<Address>> {
+    settings.validate()?;
+    let mut tokens = Vec::with_capacity(settings.tokens);
+    for index in 0..settings.tokens {
+        let args = (format!("Token {}", index), format!("TK{}", index), 18_u8);
+        tokens.push(ArbiterToken::deploy(client.clone(), args)?.send().await?);
+    }
+    let wad = U256::exp10(18);
+    for index in 0..settings.accounts {
+        let account = fixture_account(index);
+        let fund = TransactionRequest::new()
+            .to(account)
+            .value(settings.balance);
+        client
+            .send_transaction(fund, None)
+            .await
+            .with_context(|| format!("failed to fund {:?}", account))?
+            .await?;
+        for token in &tokens {
+            token.mint(account, wad * (index + 1)).send().await?.await?;
+        }
+    }
+    println!(
+        "Funded {} accounts holding {} tokens",
+        settings.accounts, settings.tokens
+    );
+    Ok(tokens.iter().map(|token| token.address()).collect())
+}
+
+/// Dumps the state of the Anvil node `client` is connected to.
+pub async fn dump<M: Middleware>(client: &M) -> Result<ChainState> {
+    let dumped: Bytes = client
+        .provider()
+        .request("anvil_dumpState", ())
+        .await
+        .context("failed to dump the node's state, is it Anvil?")?;
+    ChainState::from_bytes(&dumped)
+}
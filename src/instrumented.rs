@@ -0,0 +1,693 @@
+//! Which middleware calls each workload makes, and what they cost.
+//!
+//! [`InstrumentedMiddleware`] wraps any middleware and counts and times the
+//! `eth_` methods called on it before passing them on, and
+//! [`InstrumentedClient`] does the same for the JSON-RPC requests a
+//! [`Provider`] sends, under the name of the [`Middleware`] method each
+//! request answers. Both record into a [`CallLog`]. [`calls_middleware`] runs
+//! each workload a fixed number of times and reports the calls it made per
+//! iteration, which shows e.g. that a stateful call against Arbiter is a
+//! single `send_transaction` while against a node it also looks up the nonce,
+//! estimates gas and polls for the receipt.
+//!
+//! Where the log goes depends on the backend, see [`Instrument`]. Arbiter has
+//! no transport, so its middleware is wrapped, which is the bottom of its
+//! stack. A node's signer is rebuilt on an instrumented transport so that the
+//! lookups it does while filling a transaction, and the receipt polling of a
+//! `PendingTransaction`, are counted along with everything else.
+
+use anyhow::{bail, Ok, Result};
+use arbiter_core::middleware::RevmMiddleware;
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{JsonRpcClient, Middleware, MiddlewareError, PendingTransaction, Provider},
+    signers::LocalWallet,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip2930::AccessListWithGasUsed},
+        Address, Block, BlockId, BlockNumber, Bytes, EIP1186ProofResponse, FeeHistory, Filter, Log,
+        NameOrAddress, Signature, SyncingStatus, Transaction, TransactionReceipt, TxHash, H256,
+        U256, U64,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::BackendInfo,
+    workload::WorkloadFactory,
+};
+
+/// How often, and for how long, one method was called.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MethodCalls {
+    /// The [`Middleware`] method, e.g. "estimate_gas".
+    pub method: String,
+    pub calls: u64,
+    /// Calls that returned an error. They're included in `calls`.
+    pub errors: u64,
+    /// Time spent in the method across every call, in nanoseconds.
+    pub elapsed_ns: f64,
+}
+
+impl MethodCalls {
+    /// Mean time per call in nanoseconds.
+    pub fn mean_ns(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.elapsed_ns / self.calls as f64
+        }
+    }
+}
+
+/// The calls recorded by an [`InstrumentedMiddleware`] or an
+/// [`InstrumentedClient`], by method.
+#[derive(Debug, Default)]
+pub struct CallLog {
+    calls: Mutex<BTreeMap<String, MethodCalls>>,
+}
+
+impl CallLog {
+    /// Every method called since the last [`CallLog::reset`], in
+    /// alphabetical order.
+    pub fn calls(&self) -> Vec<MethodCalls> {
+        self.calls.lock().unwrap().values().cloned().collect()
+    }
+
+    /// The calls made to one method so far, e.g. "call".
+    pub fn get(&self, method: &str) -> Option<MethodCalls> {
+        self.calls.lock().unwrap().get(method).cloned()
+    }
+
+    /// Forgets every call recorded so far.
+    pub fn reset(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    fn add(&self, method: &str, elapsed: Duration, failed: bool) {
+        let mut calls = self.calls.lock().unwrap();
+        let entry = calls
+            .entry(method.to_string())
+            .or_insert_with(|| MethodCalls {
+                method: method.to_string(),
+                ..Default::default()
+            });
+        entry.calls += 1;
+        entry.errors += failed as u64;
+        entry.elapsed_ns += elapsed.as_nanos() as f64;
+    }
+
+    async fn record<T, E>(
+        &self,
+        method: &str,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = call.await;
+        self.add(method, start.elapsed(), result.is_err());
+        result
+    }
+}
+
+/// A [`Middleware`] that counts and times the calls made on it. Methods
+/// outside of the `eth_` namespace, such as tracing, txpool and
+/// subscriptions, are passed through uncounted. `send_transaction` is timed
+/// until the transaction is submitted, not until it's mined.
+#[derive(Debug)]
+pub struct InstrumentedMiddleware<M> {
+    inner: M,
+    log: Arc<CallLog>,
+}
+
+impl<M: Middleware> InstrumentedMiddleware<M> {
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            log: Default::default(),
+        }
+    }
+
+    /// Where the calls are recorded.
+    pub fn log(&self) -> &Arc<CallLog> {
+        &self.log
+    }
+
+    /// Every method called since the last [`InstrumentedMiddleware::reset`],
+    /// in alphabetical order.
+    pub fn calls(&self) -> Vec<MethodCalls> {
+        self.log.calls()
+    }
+
+    /// The calls made to one method so far, e.g. "call".
+    pub fn get(&self, method: &str) -> Option<MethodCalls> {
+        self.log.get(method)
+    }
+
+    /// Forgets every call recorded so far.
+    pub fn reset(&self) {
+        self.log.reset()
+    }
+
+    async fn record<T>(
+        &self,
+        method: &'static str,
+        call: impl Future<Output = Result<T, M::Error>>,
+    ) -> Result<T, InstrumentedError<M>> {
+        self.log
+            .record(method, call)
+            .await
+            .map_err(InstrumentedError)
+    }
+}
+
+/// An error from the middleware inside an [`InstrumentedMiddleware`].
+pub struct InstrumentedError<M: Middleware>(pub M::Error);
+
+impl<M: Middleware> fmt::Debug for InstrumentedError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<M: Middleware> fmt::Display for InstrumentedError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<M: Middleware> std::error::Error for InstrumentedError<M> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl<M: Middleware> MiddlewareError for InstrumentedError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        Self(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        Some(&self.0)
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Middleware for InstrumentedMiddleware<M> {
+    type Error = InstrumentedError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    async fn client_version(&self) -> Result<String, Self::Error> {
+        self.record("client_version", self.inner.client_version())
+            .await
+    }
+
+    async fn fill_transaction(
+        &self,
+        tx: &mut TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<(), Self::Error> {
+        self.record("fill_transaction", self.inner.fill_transaction(tx, block))
+            .await
+    }
+
+    async fn get_block_number(&self) -> Result<U64, Self::Error> {
+        self.record("get_block_number", self.inner.get_block_number())
+            .await
+    }
+
+    async fn send_transaction<'a, T: Into<TypedTransaction> + Send + Sync>(
+        &'a self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'a, M::Provider>, Self::Error> {
+        self.record("send_transaction", self.inner.send_transaction(tx, block))
+            .await
+    }
+
+    async fn get_block<T: Into<BlockId> + Send + Sync>(
+        &self,
+        block_hash_or_number: T,
+    ) -> Result<Option<Block<TxHash>>, Self::Error> {
+        self.record("get_block", self.inner.get_block(block_hash_or_number))
+            .await
+    }
+
+    async fn get_block_with_txs<T: Into<BlockId> + Send + Sync>(
+        &self,
+        block_hash_or_number: T,
+    ) -> Result<Option<Block<Transaction>>, Self::Error> {
+        self.record(
+            "get_block_with_txs",
+            self.inner.get_block_with_txs(block_hash_or_number),
+        )
+        .await
+    }
+
+    async fn get_transaction_count<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        self.record(
+            "get_transaction_count",
+            self.inner.get_transaction_count(from, block),
+        )
+        .await
+    }
+
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        self.record("estimate_gas", self.inner.estimate_gas(tx, block))
+            .await
+    }
+
+    async fn call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        self.record("call", self.inner.call(tx, block)).await
+    }
+
+    async fn syncing(&self) -> Result<SyncingStatus, Self::Error> {
+        self.record("syncing", self.inner.syncing()).await
+    }
+
+    async fn get_chainid(&self) -> Result<U256, Self::Error> {
+        self.record("get_chainid", self.inner.get_chainid()).await
+    }
+
+    async fn get_net_version(&self) -> Result<String, Self::Error> {
+        self.record("get_net_version", self.inner.get_net_version())
+            .await
+    }
+
+    async fn get_balance<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        self.record("get_balance", self.inner.get_balance(from, block))
+            .await
+    }
+
+    async fn get_transaction<T: Send + Sync + Into<TxHash>>(
+        &self,
+        transaction_hash: T,
+    ) -> Result<Option<Transaction>, Self::Error> {
+        self.record(
+            "get_transaction",
+            self.inner.get_transaction(transaction_hash),
+        )
+        .await
+    }
+
+    async fn get_transaction_receipt<T: Send + Sync + Into<TxHash>>(
+        &self,
+        transaction_hash: T,
+    ) -> Result<Option<TransactionReceipt>, Self::Error> {
+        self.record(
+            "get_transaction_receipt",
+            self.inner.get_transaction_receipt(transaction_hash),
+        )
+        .await
+    }
+
+    async fn get_block_receipts<T: Into<BlockNumber> + Send + Sync>(
+        &self,
+        block: T,
+    ) -> Result<Vec<TransactionReceipt>, Self::Error> {
+        self.record("get_block_receipts", self.inner.get_block_receipts(block))
+            .await
+    }
+
+    async fn get_gas_price(&self) -> Result<U256, Self::Error> {
+        self.record("get_gas_price", self.inner.get_gas_price())
+            .await
+    }
+
+    async fn estimate_eip1559_fees(
+        &self,
+        estimator: Option<fn(U256, Vec<Vec<U256>>) -> (U256, U256)>,
+    ) -> Result<(U256, U256), Self::Error> {
+        self.record(
+            "estimate_eip1559_fees",
+            self.inner.estimate_eip1559_fees(estimator),
+        )
+        .await
+    }
+
+    async fn get_accounts(&self) -> Result<Vec<Address>, Self::Error> {
+        self.record("get_accounts", self.inner.get_accounts()).await
+    }
+
+    async fn send_raw_transaction<'a>(
+        &'a self,
+        tx: Bytes,
+    ) -> Result<PendingTransaction<'a, M::Provider>, Self::Error> {
+        self.record("send_raw_transaction", self.inner.send_raw_transaction(tx))
+            .await
+    }
+
+    async fn sign<T: Into<Bytes> + Send + Sync>(
+        &self,
+        data: T,
+        from: &Address,
+    ) -> Result<Signature, Self::Error> {
+        self.record("sign", self.inner.sign(data, from)).await
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+        from: Address,
+    ) -> Result<Signature, Self::Error> {
+        self.record("sign_transaction", self.inner.sign_transaction(tx, from))
+            .await
+    }
+
+    async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, Self::Error> {
+        self.record("get_logs", self.inner.get_logs(filter)).await
+    }
+
+    async fn get_code<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        at: T,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        self.record("get_code", self.inner.get_code(at, block))
+            .await
+    }
+
+    async fn get_storage_at<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        location: H256,
+        block: Option<BlockId>,
+    ) -> Result<H256, Self::Error> {
+        self.record(
+            "get_storage_at",
+            self.inner.get_storage_at(from, location, block),
+        )
+        .await
+    }
+
+    async fn get_proof<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        locations: Vec<H256>,
+        block: Option<BlockId>,
+    ) -> Result<EIP1186ProofResponse, Self::Error> {
+        self.record("get_proof", self.inner.get_proof(from, locations, block))
+            .await
+    }
+
+    async fn fee_history<T: Into<U256> + Serialize + Send + Sync>(
+        &self,
+        block_count: T,
+        last_block: BlockNumber,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory, Self::Error> {
+        self.record(
+            "fee_history",
+            self.inner
+                .fee_history(block_count, last_block, reward_percentiles),
+        )
+        .await
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<AccessListWithGasUsed, Self::Error> {
+        self.record(
+            "create_access_list",
+            self.inner.create_access_list(tx, block),
+        )
+        .await
+    }
+}
+
+/// A [`JsonRpcClient`] that counts and times the requests sent through it.
+/// Requests are recorded under the [`Middleware`] method that sends them,
+/// e.g. `eth_getTransactionCount` as "get_transaction_count", so the calls
+/// of a node compare with those of an [`InstrumentedMiddleware`]. Methods
+/// without one keep their JSON-RPC name.
+#[derive(Clone, Debug)]
+pub struct InstrumentedClient<T> {
+    inner: T,
+    log: Arc<CallLog>,
+}
+
+impl<T: JsonRpcClient> InstrumentedClient<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            log: Default::default(),
+        }
+    }
+
+    /// Where the requests are recorded.
+    pub fn log(&self) -> &Arc<CallLog> {
+        &self.log
+    }
+}
+
+/// The [`Middleware`] method that sends the JSON-RPC request `method`.
+fn middleware_method(method: &str) -> &str {
+    match method {
+        "eth_blockNumber" => "get_block_number",
+        "eth_call" => "call",
+        "eth_chainId" => "get_chainid",
+        "eth_createAccessList" => "create_access_list",
+        "eth_estimateGas" => "estimate_gas",
+        "eth_feeHistory" => "fee_history",
+        "eth_gasPrice" => "get_gas_price",
+        "eth_getBalance" => "get_balance",
+        "eth_getBlockByHash" | "eth_getBlockByNumber" => "get_block",
+        "eth_getBlockReceipts" => "get_block_receipts",
+        "eth_getCode" => "get_code",
+        "eth_getLogs" => "get_logs",
+        "eth_getProof" => "get_proof",
+        "eth_getStorageAt" => "get_storage_at",
+        "eth_getTransactionByHash" => "get_transaction",
+        "eth_getTransactionCount" => "get_transaction_count",
+        "eth_getTransactionReceipt" => "get_transaction_receipt",
+        "eth_sendRawTransaction" => "send_raw_transaction",
+        "eth_sendTransaction" => "send_transaction",
+        "eth_sign" => "sign",
+        "eth_signTransaction" => "sign_transaction",
+        "eth_syncing" => "syncing",
+        "net_version" => "get_net_version",
+        "web3_clientVersion" => "client_version",
+        other => other,
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient> JsonRpcClient for InstrumentedClient<T> {
+    type Error = T::Error;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: std::fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        self.log
+            .record(
+                middleware_method(method),
+                self.inner.request(method, params),
+            )
+            .await
+    }
+}
+
+/// Middleware that can be rebuilt to record the calls it makes at the
+/// bottom of its stack.
+pub trait Instrument: Middleware + 'static {
+    type Instrumented: Middleware + 'static;
+
+    /// The same client recording into the returned log.
+    fn instrument(client: Arc<Self>) -> (Arc<Self::Instrumented>, Arc<CallLog>);
+}
+
+impl Instrument for RevmMiddleware {
+    type Instrumented = InstrumentedMiddleware<Arc<RevmMiddleware>>;
+
+    fn instrument(client: Arc<Self>) -> (Arc<Self::Instrumented>, Arc<CallLog>) {
+        let client = InstrumentedMiddleware::new(client);
+        let log = client.log().clone();
+        (Arc::new(client), log)
+    }
+}
+
+impl<T: JsonRpcClient + Clone + 'static> Instrument for SignerMiddleware<Provider<T>, LocalWallet> {
+    type Instrumented = SignerMiddleware<Provider<InstrumentedClient<T>>, LocalWallet>;
+
+    fn instrument(client: Arc<Self>) -> (Arc<Self::Instrumented>, Arc<CallLog>) {
+        let transport = InstrumentedClient::new(client.inner().as_ref().clone());
+        let log = transport.log().clone();
+        let provider = Provider::new(transport).interval(client.inner().get_interval());
+        let signer = SignerMiddleware::new(provider, client.signer().clone());
+        (Arc::new(signer), log)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallSettings {
+    /// Times to run each workload.
+    pub iterations: u64,
+}
+
+impl Default for CallSettings {
+    fn default() -> Self {
+        Self { iterations: 100 }
+    }
+}
+
+impl CallSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.iterations == 0 {
+            bail!("at least one iteration is needed");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadCalls {
+    /// The backend and workload together, e.g. "Arbiter Stateful Call".
+    pub label: String,
+    pub workload: String,
+    pub iterations: u64,
+    /// Every method the workload called while running, setup excluded.
+    pub methods: Vec<MethodCalls>,
+}
+
+impl WorkloadCalls {
+    /// The calls made to one method, e.g. "call".
+    pub fn get(&self, method: &str) -> Option<&MethodCalls> {
+        self.methods.iter().find(|calls| calls.method == method)
+    }
+
+    /// How many times an iteration calls `method` on average.
+    pub fn per_iteration(&self, method: &str) -> f64 {
+        self.get(method)
+            .map_or(0.0, |calls| calls.calls as f64 / self.iterations as f64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallResults {
+    pub backend: BackendInfo,
+    pub workloads: Vec<WorkloadCalls>,
+}
+
+impl CallResults {
+    /// The result for a workload by name, e.g. "Lookups".
+    pub fn get(&self, workload: &str) -> Option<&WorkloadCalls> {
+        self.workloads
+            .iter()
+            .find(|result| result.workload == workload)
+    }
+}
+
+/// Runs every workload from `workloads` `settings.iterations` times on
+/// `client`, instrumented, recording the calls each one makes.
+pub async fn calls_middleware<M: Instrument>(
+    client: Arc<M>,
+    label: &str,
+    workloads: &impl WorkloadFactory,
+    settings: &CallSettings,
+) -> Result<CallResults> {
+    settings.validate()?;
+    let mut results = CallResults {
+        backend: BackendInfo::query(label, &*client).await,
+        workloads: Vec::new(),
+    };
+    let (client, log) = M::instrument(client);
+    let mut workloads = workloads.build()?;
+    for (workload, _) in workloads.iter_mut() {
+        workload.setup(client.clone()).await?;
+        let name = workload.name().to_string();
+        println!("{} {}: recording {} runs", label, name, settings.iterations);
+        log.reset();
+        for _ in 0..settings.iterations {
+            workload.run().await?;
+        }
+        let methods = log.calls();
+        workload.teardown().await?;
+        results.workloads.push(WorkloadCalls {
+            label: format!("{} {}", label, name),
+            workload: name,
+            iterations: settings.iterations,
+            methods,
+        });
+    }
+    Ok(results)
+}
+
+/// The call counts of a run, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallReport {
+    pub schema_version: u32,
+    pub results: Vec<CallResults>,
+}
+
+impl CallReport {
+    pub fn new(results: Vec<CallResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A row per method each benchmark called.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from(
+            "| Benchmark | Method | Calls per iteration | Mean | Errors |\n\
+             | --- | --- | ---: | ---: | ---: |\n",
+        );
+        for workload in self.results.iter().flat_map(|results| &results.workloads) {
+            for calls in &workload.methods {
+                writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} |",
+                    workload.label,
+                    calls.method,
+                    workload.per_iteration(&calls.method),
+                    format_ns(calls.mean_ns()),
+                    calls.errors
+                )
+                .unwrap();
+            }
+        }
+        md
+    }
+}
@@ -1,58 +1,235 @@
-// Todo allow for users to pick the contracts they want to benchmark with
+use anyhow::{bail, Ok, Result};
+use ethers::providers::Middleware;
 
-use anyhow::{Ok, Result};
-use bench_functions::create_call;
-use ethers::{providers::Middleware, utils::AnvilInstance};
+use std::{
+    cell::RefCell,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use std::sync::Arc;
-
-use crate::bench_functions::{lookup, stateful_call, stateless_call};
 use criterion::async_executor::FuturesExecutor;
-use criterion::Criterion;
+use criterion::{measurement::WallTime, Bencher, BenchmarkGroup, BenchmarkId, Criterion};
 
-mod bench_functions;
+pub mod artifacts;
+pub mod backend;
+pub mod bench_functions;
 mod bindings;
+pub mod compare;
+pub mod compute;
+pub mod depth;
+pub mod fixture;
+pub mod instrumented;
+pub mod mock_node;
+pub mod network;
+pub mod open_loop;
+pub mod phases;
+pub mod precompiles;
+pub mod regression;
+pub mod report;
+pub mod results;
+pub mod scaling;
+pub mod snapshot;
+pub mod stack;
+pub mod suite;
+pub mod throughput;
 mod utils;
+pub mod verify;
+pub mod workload;
+
+pub use backend::{
+    AnvilBackend, AnvilHttpBackend, AnvilIpcBackend, AnvilNode, AnvilWsBackend, ArbiterBackend,
+    Backend, MockBackend, RpcBackend,
+};
+pub use instrumented::InstrumentedMiddleware;
+pub use mock_node::MockNode;
+pub use results::{BackendInfo, BenchResults, Sample, Summary, WorkloadResult};
+pub use workload::{BenchSettings, Workload, WorkloadRegistry};
+
+use snapshot::{Reset, Revertible};
 
 pub async fn bench_middleware<M: Middleware + 'static>(
     c: &mut Criterion,
     client: Arc<M>,
     label: &str,
-    _anvil: Option<AnvilInstance>,
-) -> Result<()> {
+    mut workloads: WorkloadRegistry<M>,
+) -> Result<BenchResults> {
     println!("Start bench_middleware with label: {}", label);
-    // these are the contracts we are benching against
-    // this can happily change if people want to bench against more general contracts
-    let (math_contract, token_contract) =
-        utils::deploy_contracts_for_benchmarks(client.clone()).await?;
-
-    c.bench_function(&format!("{} Stateful Call", label), |b| {
-        b.to_async(FuturesExecutor).iter(|| async {
-            stateful_call(token_contract.clone(), client.default_sender().unwrap())
-                .await
-                .unwrap();
-        })
-    });
-    c.bench_function(&format!("{} Stateless Call", label), |b| {
-        b.to_async(FuturesExecutor).iter(|| async {
-            stateless_call(math_contract.clone()).await.unwrap();
-        })
-    });
-    c.bench_function(&format!(" {} Create", label), |b| {
-        b.to_async(FuturesExecutor).iter(|| async {
-            create_call(client.clone()).await.unwrap();
-        })
-    });
-    c.bench_function(&format!("{} Lookups", label), |b| {
-        b.to_async(FuturesExecutor).iter(|| async {
-            lookup(token_contract.clone()).await.unwrap();
-        })
-    });
+    let mut results = BenchResults {
+        backend: BackendInfo::query(label, &*client).await,
+        workloads: Vec::new(),
+    };
+    let snapshots = workloads.take_snapshots();
+    for (workload, settings) in workloads.iter_mut() {
+        workload.setup(client.clone()).await?;
+        let name = workload.name().to_string();
+        let reset = match (settings.reset, &snapshots) {
+            (Some(reset), Some(snapshots)) => {
+                let slots = workload.written_slots();
+                if slots.is_empty() && snapshots.declared_slots_only() {
+                    bail!(
+                        "{} only resets the storage slots a workload declares, and {} declares none",
+                        label,
+                        name
+                    );
+                }
+                snapshots.snapshot(&slots).await?;
+                Some((reset, &**snapshots))
+            }
+            (Some(_), None) => bail!("{} can't reset state for {}", label, name),
+            (None, _) => None,
+        };
+        let runner = &**workload;
+        // a group per workload so that each one starts from `c`'s configuration
+        let mut group = c.benchmark_group(label);
+        settings.apply(&mut group);
+        let result = record(
+            &mut group,
+            None,
+            label,
+            &name,
+            &settings,
+            |iters| async move {
+                match reset {
+                    None => {
+                        let start = Instant::now();
+                        for _ in 0..iters {
+                            runner.run().await?;
+                        }
+                        Ok(start.elapsed())
+                    }
+                    Some((Reset::Batch, snapshots)) => {
+                        snapshots.revert().await?;
+                        let start = Instant::now();
+                        for _ in 0..iters {
+                            runner.run().await?;
+                        }
+                        Ok(start.elapsed())
+                    }
+                    Some((Reset::Iteration, snapshots)) => {
+                        let mut elapsed = Duration::ZERO;
+                        for _ in 0..iters {
+                            snapshots.revert().await?;
+                            let start = Instant::now();
+                            runner.run().await?;
+                            elapsed += start.elapsed();
+                        }
+                        Ok(elapsed)
+                    }
+                }
+            },
+        );
+        group.finish();
+        let mut result = result?;
+        // a backend that can't report gas leaves it unset rather than failing
+        if let Some(result) = &mut result {
+            // from the state the samples started from
+            if let Some((_, snapshots)) = reset {
+                snapshots.revert().await?;
+            }
+            result.gas = workload.gas().await.ok().flatten();
+        }
+        workload.teardown().await?;
+        results.workloads.extend(result);
+    }
     println!("End bench_middleware with label: {}", label);
-    if let Some(anvil) = _anvil {
-        drop(anvil);
+    Ok(results)
+}
+
+/// Benchmarks `name` in `group`, under `id` if it's parameterized, where
+/// `run(iters)` runs it `iters` times and returns how long that took. The
+/// result is labelled "`label` `name`" and has no gas, or is `None` when a
+/// Criterion filter skipped it. The first error `run` returns stops it from
+/// being run again and is returned once Criterion is done.
+pub(crate) fn record<F, Fut>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    id: Option<BenchmarkId>,
+    label: &str,
+    name: &str,
+    settings: &BenchSettings,
+    mut run: F,
+) -> Result<Option<WorkloadResult>>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<Duration>>,
+{
+    let recorded = RefCell::new(Vec::new());
+    let failed: RefCell<Option<anyhow::Error>> = RefCell::new(None);
+    let routine = |b: &mut Bencher<'_, WallTime>| {
+        b.to_async(FuturesExecutor).iter_custom(|iters| {
+            let (recorded, failed) = (&recorded, &failed);
+            let timed = match failed.borrow().is_some() {
+                true => None,
+                false => Some(run(iters)),
+            };
+            async move {
+                // Criterion can't be stopped, so the rest of its iterations
+                // take a made up second each to get them over with quickly
+                let skipped = Duration::from_secs(iters);
+                match timed {
+                    Some(timed) => match timed.await {
+                        Result::Ok(elapsed) => {
+                            recorded.borrow_mut().push(Sample {
+                                iterations: iters,
+                                elapsed_ns: elapsed.as_nanos() as f64,
+                            });
+                            elapsed
+                        }
+                        Err(err) => {
+                            *failed.borrow_mut() = Some(err);
+                            skipped
+                        }
+                    },
+                    None => skipped,
+                }
+            }
+        })
+    };
+    match id {
+        Some(id) => group.bench_function(id, routine),
+        None => group.bench_function(name, routine),
+    };
+    if let Some(err) = failed.into_inner() {
+        return Err(err.context(format!("{} {} failed", label, name)));
+    }
+    // warm-up runs come first, Criterion's samples are the last ones
+    let mut samples = recorded.into_inner();
+    samples.drain(..samples.len().saturating_sub(settings.sample_size()));
+    // nothing is recorded for benchmarks skipped by a Criterion filter
+    if samples.is_empty() {
+        return Ok(None);
     }
-    Ok(())
+    Ok(Some(WorkloadResult {
+        label: format!("{} {}", label, name),
+        workload: name.to_string(),
+        iterations: samples.iter().map(|sample| sample.iterations).sum(),
+        summary: Summary::from_samples(&samples),
+        samples,
+        gas: None,
+    }))
+}
+
+/// Starts `backend`, benchmarks `workloads` against it with
+/// [`bench_middleware`] and tears it down again.
+pub async fn bench_backend<B: Backend>(
+    c: &mut Criterion,
+    backend: &mut B,
+    mut workloads: WorkloadRegistry<B::Middleware>,
+) -> Result<BenchResults>
+where
+    B::Middleware: Revertible,
+{
+    let client = backend.start().await?;
+    workloads.snapshots(B::Middleware::snapshots(client.clone()));
+    let name = backend.name().to_string();
+    let results = bench_middleware(c, client.clone(), &name, workloads).await;
+    let info = backend.info(&client).await;
+    // torn down even when a workload failed, as in `Suite::run_on`
+    let torn_down = backend.teardown().await;
+    let mut results = results?;
+    torn_down?;
+    results.backend = info;
+    Ok(results)
 }
 
 #[allow(unused_imports)]
@@ -73,87 +250,120 @@ mod tests {
         middleware::SignerMiddleware,
         providers::{Http, Provider},
         signers::{LocalWallet, Signer, Wallet},
-        types::Address,
+        types::{Address, H256, U256},
         utils::{Anvil, AnvilInstance},
     };
 
     #[tokio::test]
     async fn arbiter() {
-        // get arbiter middleware
-        let environment = EnvironmentBuilder::new().build();
-        let arbiter_middleware = RevmMiddleware::new(&environment, Some("name")).unwrap();
-
         let mut c = Criterion::default().configure_from_args();
 
-        let arbiter_results = bench_middleware(&mut c, arbiter_middleware, "Arbiter", None).await;
+        let arbiter_results = bench_backend(
+            &mut c,
+            &mut ArbiterBackend::default(),
+            WorkloadRegistry::builtin(),
+        )
+        .await;
         if let Err(err) = &arbiter_results {
             eprintln!("Error with Arbiter middleware: {:?}", err);
         }
-        assert!(arbiter_results.is_ok());
-        // let arbiter_results = arbiter_results.unwrap();
-        // let anvil_results = anvil_results.unwrap();
+        let arbiter_results = arbiter_results.unwrap();
+        assert_eq!(arbiter_results.backend.name, "Arbiter");
+        assert_eq!(
+            arbiter_results.backend.transport.as_deref(),
+            Some("in-process")
+        );
+        assert_eq!(arbiter_results.workloads.len(), 4);
+        let stateful = arbiter_results.get("Stateful Call").unwrap();
+        assert_eq!(stateful.label, "Arbiter Stateful Call");
+        assert!(!stateful.samples.is_empty());
+        assert!(stateful.summary.min > 0.0);
+        assert!(stateful.summary.min <= stateful.summary.median);
+        assert!(stateful.summary.median <= stateful.summary.max);
+        // gas comes from receipts, Arbiter can't estimate it for calls
+        assert!(stateful.gas.unwrap() > 21_000);
+        assert!(arbiter_results.get("Create").unwrap().gas.is_some());
+        assert_eq!(arbiter_results.get("Lookups").unwrap().gas, None);
     }
 
     #[tokio::test]
     async fn anvil() {
-        let anvil = Anvil::new().spawn();
-        // Create a client
-        let provider = Provider::<Http>::try_from(anvil.endpoint())
-            .unwrap()
-            .interval(Duration::ZERO);
+        let mut backend = AnvilHttpBackend::default();
+        let anvil_middleware = backend.start().await.unwrap();
 
         // check client is working
-        let block = provider.get_block_number().await;
+        let block = anvil_middleware.get_block_number().await;
         assert!(block.is_ok());
         let block = block.unwrap();
         assert_eq!(block, 0_u64.into());
 
-        let wallet = LocalWallet::from(anvil.keys()[0].clone());
         assert_eq!(
-            wallet.address(),
+            anvil_middleware.address(),
             Address::from_str("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap()
         );
-        let anvil_middleware = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(anvil.chain_id()),
-        ));
 
         let mut c = Criterion::default().configure_from_args();
-        let anvil_results = bench_middleware(&mut c, anvil_middleware, "Anvil", Some(anvil)).await;
+        let anvil_results = bench_middleware(
+            &mut c,
+            anvil_middleware,
+            "Anvil",
+            WorkloadRegistry::builtin(),
+        )
+        .await;
+        backend.teardown().await.unwrap();
         if let Err(err) = &anvil_results {
             eprintln!("Error with Anvil middleware: {:?}", err);
         }
         assert!(anvil_results.is_ok());
     }
 
+    // the transports share one node, as in a suite listing all three. WS and
+    // IPC read responses on a task of their own, which needs a runtime
+    // thread Criterion's executor isn't blocking, see `mock_node`
+    #[tokio::test(flavor = "multi_thread")]
+    async fn anvil_transports() {
+        let node = AnvilNode::spawn();
+        let mut c = Criterion::default().configure_from_args();
+        fn lookups<M: Middleware + 'static>() -> WorkloadRegistry<M> {
+            WorkloadRegistry::builtin().select(&["Lookups"]).unwrap()
+        }
+        let http = bench_backend(&mut c, &mut AnvilHttpBackend::on(node.clone()), lookups())
+            .await
+            .unwrap();
+        let ws = bench_backend(&mut c, &mut AnvilWsBackend::on(node.clone()), lookups())
+            .await
+            .unwrap();
+        let ipc = bench_backend(&mut c, &mut AnvilIpcBackend::on(node.clone()), lookups())
+            .await
+            .unwrap();
+        assert!(node.ipc_path().exists());
+        for (results, name, transport) in [
+            (http, "Anvil", "http"),
+            (ws, "Anvil WS", "ws"),
+            (ipc, "Anvil IPC", "ipc"),
+        ] {
+            assert_eq!(results.backend.name, name);
+            assert_eq!(results.backend.transport.as_deref(), Some(transport));
+            assert_eq!(results.backend.chain_id, Some(31337));
+            assert!(results.get("Lookups").unwrap().summary.mean > 0.0);
+        }
+        let ipc = node.ipc_path().to_path_buf();
+        drop(node);
+        assert!(!ipc.exists());
+    }
+
     #[tokio::test]
     async fn debugging_anvil_deployments() {
         // get anvil middleware
-
-        let anvil = Anvil::new().spawn();
-        // Create a client
-        let provider = Provider::<Http>::try_from(anvil.endpoint())
-            .unwrap()
-            .interval(Duration::ZERO);
+        let mut backend = AnvilHttpBackend::default();
+        let anvil_middleware = backend.start().await.unwrap();
 
         // check client is working
-        let block = provider.get_block_number().await;
+        let block = anvil_middleware.get_block_number().await;
         assert!(block.is_ok());
         let block = block.unwrap();
         assert_eq!(block, 0_u64.into());
 
-        let wallet = LocalWallet::from(anvil.keys()[0].clone());
-        assert_eq!(
-            wallet.address(),
-            Address::from_str("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap()
-        );
-        // let wallet: LocalWallet = anvil.keys()[0].clone().into();
-        let anvil_middleware = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(anvil.chain_id()),
-        ));
-
-        println!("{:?}#", anvil.port());
         // or the other arbiter bindings
         let math = ArbiterMath::deploy(anvil_middleware.clone(), ())
             .unwrap()
@@ -163,4 +373,1390 @@ mod tests {
         println!("1");
         println!("{:#?}", math.address());
     }
+
+    #[tokio::test]
+    async fn custom_workload() {
+        struct BlockNumber {
+            client: Option<Arc<RevmMiddleware>>,
+        }
+
+        #[async_trait::async_trait]
+        impl Workload<RevmMiddleware> for BlockNumber {
+            fn name(&self) -> &str {
+                "Block Number"
+            }
+
+            async fn setup(&mut self, client: Arc<RevmMiddleware>) -> Result<()> {
+                self.client = Some(client);
+                Ok(())
+            }
+
+            async fn run(&self) -> Result<()> {
+                self.client.as_ref().unwrap().get_block_number().await?;
+                Ok(())
+            }
+        }
+
+        let mut backend = ArbiterBackend::default();
+        let arbiter_middleware = backend.start().await.unwrap();
+
+        let mut workloads = WorkloadRegistry::builtin().select(&["Lookups"]).unwrap();
+        workloads.register(BlockNumber { client: None });
+        assert_eq!(workloads.names(), vec!["Lookups", "Block Number"]);
+
+        let mut c = Criterion::default().configure_from_args();
+        let results =
+            bench_middleware(&mut c, arbiter_middleware.clone(), "Arbiter", workloads).await;
+        assert!(results.is_ok());
+
+        struct Failing;
+
+        #[async_trait::async_trait]
+        impl Workload<RevmMiddleware> for Failing {
+            fn name(&self) -> &str {
+                "Failing"
+            }
+
+            async fn setup(&mut self, _client: Arc<RevmMiddleware>) -> Result<()> {
+                Ok(())
+            }
+
+            async fn run(&self) -> Result<()> {
+                bail!("out of gas")
+            }
+        }
+
+        // the error is returned rather than panicking inside Criterion
+        let mut workloads = WorkloadRegistry::new();
+        workloads.register(Failing);
+        workloads.defaults(BenchSettings {
+            sample_size: Some(10),
+            measurement_time: Some(0.2),
+            warm_up_time: Some(0.1),
+            ..Default::default()
+        });
+        let failed = bench_middleware(&mut c, arbiter_middleware, "Arbiter", workloads)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            format!("{:#}", failed),
+            "Arbiter Failing failed: out of gas"
+        );
+    }
+
+    /// Lays out a Counter artifact under `<root>/build` the way `forge build` would.
+    #[cfg(test)]
+    fn write_counter_project(root: &std::path::Path) {
+        use crate::bindings::counter::{COUNTER_ABI, COUNTER_BYTECODE};
+
+        let source = root.join("build").join("Counter.sol");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(
+            root.join("foundry.toml"),
+            "[profile.default]\nout = \"build\"\n",
+        )
+        .unwrap();
+        let artifact = serde_json::json!({
+            "abi": &*COUNTER_ABI,
+            "bytecode": { "object": COUNTER_BYTECODE.to_string() },
+        });
+        std::fs::write(source.join("Counter.json"), artifact.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn foundry_artifacts() {
+        use crate::artifacts::{ArtifactCall, Artifacts};
+
+        let root =
+            std::env::temp_dir().join(format!("benchlayer-artifacts-{}", std::process::id()));
+        write_counter_project(&root);
+        let artifacts = Artifacts::from_foundry_project(&root).unwrap();
+        assert_eq!(artifacts.contracts().unwrap(), vec!["Counter"]);
+        let counter = artifacts.get("Counter").unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let mut workloads = WorkloadRegistry::new();
+        workloads
+            .register(ArtifactCall::new(
+                "Set Number",
+                counter.clone(),
+                "setNumber(uint256)",
+                vec!["42".to_string()],
+            ))
+            .register(ArtifactCall::new("Number", counter, "number", vec![]));
+
+        let mut backend = ArbiterBackend::default();
+        let arbiter_middleware = backend.start().await.unwrap();
+        let mut c = Criterion::default().configure_from_args();
+        let results = bench_middleware(&mut c, arbiter_middleware, "Arbiter", workloads).await;
+        assert!(results.is_ok());
+    }
+
+    #[test]
+    fn suite_validation() {
+        use crate::suite::Suite;
+
+        let suite = Suite::parse(include_str!("../suites/counter.toml"), ".").unwrap();
+        assert_eq!(suite.backends, vec!["arbiter", "anvil"]);
+        assert_eq!(suite.benchmarks[0].settings().sample_size, Some(20));
+        let transports =
+            "backends = [\"anvil\", \"anvil-ws\", \"anvil-ipc\"]\nworkloads = [\"Lookups\"]";
+        assert!(Suite::parse(transports, ".").is_ok());
+        let rpc = "backends = [\"rpc\", \"mock\"]\nworkloads = [\"Lookups\"]\n\
+                   [rpc]\nurl = \"http://localhost:8545\"\nkey_env = \"BENCH_KEY\"";
+        assert!(Suite::parse(rpc, ".").is_ok());
+        let reset = "backends = [\"arbiter\"]\nworkloads = [\"Lookups\"]\nreset = \"batch\"";
+        assert_eq!(
+            Suite::parse(reset, ".").unwrap().settings().reset,
+            Some(crate::snapshot::Reset::Batch)
+        );
+
+        let invalid = [
+            "backends = [\"geth\"]\nworkloads = [\"Lookups\"]",
+            "backends = [\"arbiter\"]",
+            "backends = [\"arbiter\"]\nworkloads = [\"Lookup\"]",
+            "backends = [\"arbiter\", \"arbiter\"]\nworkloads = [\"Lookups\"]",
+            "backends = [\"anvil-http\"]\nworkloads = [\"Lookups\"]",
+            "backends = [\"rpc\"]\nworkloads = [\"Lookups\"]",
+            "backends = [\"rpc\"]\nworkloads = [\"Lookups\"]\n[rpc]\nurl = \"http://localhost:8545\"",
+            "backends = [\"arbiter\"]\n[[benchmarks]]\nname = \"a\"\ncontract = \"c\"\nfunction = \"f\"",
+            "backends = [\"arbiter\"]\nworkloads = [\"Lookups\"]\nsample_count = 3",
+            "backends = [\"arbiter\"]\nworkloads = [\"Lookups\"]\nreset = \"sample\"",
+        ];
+        for raw in invalid {
+            assert!(Suite::parse(raw, ".").is_err(), "{}", raw);
+        }
+    }
+
+    #[tokio::test]
+    async fn suite_run() {
+        use crate::suite::Suite;
+
+        let root = std::env::temp_dir().join(format!("benchlayer-suite-{}", std::process::id()));
+        write_counter_project(&root);
+        let path = root.join("suite.toml");
+        std::fs::write(
+            &path,
+            include_str!("../suites/counter.toml").replace("\"anvil\"", ""),
+        )
+        .unwrap();
+        // a fixture is found next to the suite rather than where we're run
+        std::fs::write(root.join("fixture.json"), "{}").unwrap();
+        let stateful = root.join("stateful.toml");
+        std::fs::write(
+            &stateful,
+            "backends = [\"arbiter\"]\nworkloads = [\"Lookups\"]\nstate = \"fixture.json\"",
+        )
+        .unwrap();
+        let loaded = Suite::load(&stateful);
+        let suite = Suite::load(&path).unwrap();
+        let mut c = Criterion::default().configure_from_args();
+        let results = suite.run(&mut c).await;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            loaded.unwrap().state.as_deref(),
+            Some(std::path::Path::new("fixture.json"))
+        );
+        let results = results.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0]
+                .workloads
+                .iter()
+                .map(|result| result.label.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Arbiter Lookups",
+                "Arbiter Set Number",
+                "Arbiter Increment",
+                "Arbiter Number"
+            ]
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let samples: Vec<Sample> = (1..=10)
+            .map(|i| Sample {
+                iterations: 2,
+                elapsed_ns: 20.0 * i as f64,
+            })
+            .collect();
+        let summary = Summary::from_samples(&samples);
+        assert_eq!(summary.mean, 55.0);
+        assert_eq!(summary.median, 55.0);
+        assert_eq!(summary.min, 10.0);
+        assert_eq!(summary.max, 100.0);
+        assert!((summary.p90 - 91.0).abs() < 1e-9);
+        assert!((summary.std_dev - 30.276503540974915).abs() < 1e-9);
+        assert_eq!(Summary::from_samples(&[]), Summary::default());
+    }
+
+    #[test]
+    fn report_formats() {
+        use crate::report::Report;
+
+        let result = |backend: &str, workload: &str, ns: f64| WorkloadResult {
+            label: format!("{} {}", backend, workload),
+            workload: workload.to_string(),
+            samples: vec![Sample {
+                iterations: 1,
+                elapsed_ns: ns,
+            }],
+            iterations: 1,
+            summary: Summary::from_times(&[ns]),
+            gas: None,
+        };
+        let with_gas = |mut result: WorkloadResult, gas| {
+            result.gas = Some(gas);
+            result
+        };
+        let report = Report::new(vec![
+            BenchResults {
+                backend: BackendInfo {
+                    name: "Arbiter".to_string(),
+                    ..Default::default()
+                },
+                workloads: vec![
+                    with_gas(result("Arbiter", "Stateful Call", 1_500.0), 46_000),
+                    with_gas(result("Arbiter", "Lookups", 800.0), 23_000),
+                ],
+            },
+            BenchResults {
+                backend: BackendInfo {
+                    name: "Anvil".to_string(),
+                    transport: Some("http".to_string()),
+                    network: None,
+                    chain_id: Some(31337),
+                    client_version: Some("anvil/v0.2.0".to_string()),
+                },
+                workloads: vec![with_gas(result("Anvil", "Lookups", 2_000_000.0), 24_000)],
+            },
+        ]);
+        assert_eq!(report.backends(), vec!["Arbiter", "Anvil"]);
+        assert_eq!(report.workloads(), vec!["Stateful Call", "Lookups"]);
+        assert_eq!(
+            report.get("Anvil", "Lookups").unwrap().label,
+            "Anvil Lookups"
+        );
+
+        let json = report.to_json().unwrap();
+        assert_eq!(Report::from_json(&json).unwrap(), report);
+        assert!(
+            Report::from_json(&json.replace("\"schema_version\": 1", "\"schema_version\": 2"))
+                .is_err()
+        );
+
+        let csv = report.to_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].starts_with("backend,workload,label,"));
+        assert!(rows[3]
+            .starts_with("Anvil,Lookups,Anvil Lookups,http,,31337,anvil/v0.2.0,1,1,2000000,"));
+        assert!(rows[0].ends_with(",gas"));
+        assert!(rows[3].ends_with(",24000"));
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| Workload | Arbiter | Anvil |"));
+        assert!(markdown.contains("| Stateful Call | 1.50 µs ± 0.00 ns | - |"));
+        assert!(markdown.contains("| Lookups | 800.00 ns ± 0.00 ns | 2.00 ms ± 0.00 ns |"));
+        assert!(markdown.contains(
+            "### Anvil\n\n- Transport: http\n- Client: `anvil/v0.2.0`\n- Chain id: 31337\n\n"
+        ));
+
+        // only Lookups ran on both, and the backends disagree on it
+        assert_eq!(report.gas_mismatches(), vec!["Lookups"]);
+        assert!(markdown.contains("| Stateful Call | 46000 | - |\n| Lookups | 23000 | 24000 |"));
+        assert!(markdown.contains("**Gas differs between backends for Lookups.**"));
+        assert!(markdown.contains("| 1 | 24000 |\n"));
+    }
+
+    #[test]
+    fn speedups() {
+        use crate::compare::{CompareOptions, Comparison, Verdict};
+        use crate::report::Report;
+        use crate::suite::Suite;
+
+        let result = |workload: &str, times: &[f64]| {
+            let samples: Vec<Sample> = times
+                .iter()
+                .map(|&ns| Sample {
+                    iterations: 1,
+                    elapsed_ns: ns,
+                })
+                .collect();
+            WorkloadResult {
+                label: workload.to_string(),
+                workload: workload.to_string(),
+                iterations: samples.len() as u64,
+                summary: Summary::from_samples(&samples),
+                samples,
+                gas: None,
+            }
+        };
+        let backend = |name: &str, workloads| BenchResults {
+            backend: BackendInfo {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            workloads,
+        };
+        let slow: Vec<f64> = (0..50).map(|i| 1_000.0 + (i % 7) as f64 * 10.0).collect();
+        let fast: Vec<f64> = slow.iter().map(|ns| ns / 10.0).collect();
+        let report = Report::new(vec![
+            backend(
+                "Arbiter",
+                vec![
+                    result("Stateful Call", &fast),
+                    result("Lookups", &slow),
+                    result("Create", &[100.0]),
+                ],
+            ),
+            backend(
+                "Anvil",
+                vec![
+                    result("Stateful Call", &slow),
+                    result("Lookups", &slow),
+                    result("Create", &[1_000.0]),
+                ],
+            ),
+        ]);
+
+        let comparison = Comparison::new(&report, "anvil", CompareOptions::default()).unwrap();
+        assert_eq!(comparison.baseline, "Anvil");
+        let verdicts: Vec<_> = comparison
+            .speedups
+            .iter()
+            .map(|s| (s.workload.as_str(), s.backend.as_str(), s.verdict))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ("Stateful Call", "Arbiter", Verdict::Faster),
+                ("Lookups", "Arbiter", Verdict::Indistinguishable),
+                ("Create", "Arbiter", Verdict::TooFewSamples),
+            ]
+        );
+        let stateful = &comparison.speedups[0];
+        assert!((stateful.speedup - 10.0).abs() < 1e-9);
+        assert!(stateful.lower <= 10.0 && 10.0 <= stateful.upper);
+
+        let reversed = Comparison::new(&report, "Arbiter", CompareOptions::default()).unwrap();
+        assert_eq!(reversed.speedups[0].verdict, Verdict::Slower);
+        assert!(comparison
+            .to_markdown()
+            .contains("| Stateful Call | Arbiter |"));
+        assert!(Comparison::new(&report, "geth", CompareOptions::default()).is_err());
+
+        // backends are given by key, which isn't their name once it's two words
+        let transports = Report::new(vec![
+            backend("Anvil", vec![result("Stateful Call", &slow)]),
+            backend("Anvil WS", vec![result("Stateful Call", &fast)]),
+        ]);
+        assert!(Comparison::new(&transports, "anvil-ws", CompareOptions::default()).is_err());
+        let suite = Suite::builtin(
+            vec!["anvil".to_string(), "anvil-ws".to_string()],
+            Vec::new(),
+        );
+        let name = suite.backend_name("anvil-ws").unwrap();
+        let comparison = Comparison::new(&transports, name, CompareOptions::default()).unwrap();
+        assert_eq!(comparison.baseline, "Anvil WS");
+        assert_eq!(comparison.speedups[0].verdict, Verdict::Slower);
+        assert!(suite.backend_name("arbiter").is_err());
+        assert_eq!(crate::suite::backend_name("anvil-ipc"), Some("Anvil IPC"));
+    }
+
+    #[test]
+    fn regression_gate() {
+        use crate::regression::{Gate, Threshold};
+        use crate::report::Report;
+
+        let run = |stateful: f64, lookups: f64| {
+            let result = |workload: &str, ns: f64| {
+                let samples: Vec<Sample> = (0..20)
+                    .map(|i| Sample {
+                        iterations: 1,
+                        elapsed_ns: ns + (i % 5) as f64,
+                    })
+                    .collect();
+                WorkloadResult {
+                    label: format!("Arbiter {}", workload),
+                    workload: workload.to_string(),
+                    iterations: samples.len() as u64,
+                    summary: Summary::from_samples(&samples),
+                    samples,
+                    gas: None,
+                }
+            };
+            Report::new(vec![BenchResults {
+                backend: BackendInfo {
+                    name: "Arbiter".to_string(),
+                    ..Default::default()
+                },
+                workloads: vec![
+                    result("Stateful Call", stateful),
+                    result("Lookups", lookups),
+                ],
+            }])
+        };
+        let baseline = run(1_000.0, 1_000.0);
+        let current = run(1_200.0, 1_030.0);
+
+        let gate = Gate::new(&baseline, &current, "5%".parse().unwrap());
+        let regressed: Vec<_> = gate.regressions().map(|c| c.label.as_str()).collect();
+        assert_eq!(regressed, vec!["Arbiter Stateful Call"]);
+        assert!(!gate.passed());
+
+        let gate = Gate::new(&baseline, &current, "significant".parse().unwrap());
+        assert_eq!(gate.regressions().count(), 2);
+        assert!(Gate::new(&baseline, &baseline, Threshold::Significant(0.99)).passed());
+
+        // a workload dropped from the run fails the gate rather than passing it
+        let mut partial = baseline.clone();
+        partial.results[0].workloads.pop();
+        let gate = Gate::new(&baseline, &partial, "5%".parse().unwrap());
+        assert_eq!(gate.missing, vec!["Arbiter Lookups"]);
+        assert_eq!(gate.regressions().count(), 0);
+        assert!(!gate.passed());
+        assert!(gate
+            .to_markdown()
+            .contains("| Arbiter Lookups | - | - | - | missing |"));
+        let gate = Gate::new(&partial, &baseline, "5%".parse().unwrap());
+        assert_eq!(gate.unmatched, vec!["Arbiter Lookups"]);
+        assert!(gate.passed());
+
+        assert_eq!(
+            "2.5%".parse::<Threshold>().unwrap(),
+            Threshold::Percent(2.5)
+        );
+        assert_eq!(
+            "significant:0.99".parse::<Threshold>().unwrap(),
+            Threshold::Significant(0.99)
+        );
+        for invalid in ["-1%", "significant:2", "5", "fast"] {
+            assert!(invalid.parse::<Threshold>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn throughput() {
+        use crate::{
+            suite::Suite,
+            throughput::{throughput_middleware, ThroughputSettings},
+            workload::WorkloadFactory,
+        };
+
+        let mut backend = ArbiterBackend::default();
+        let arbiter_middleware = backend.start().await.unwrap();
+        let settings = ThroughputSettings {
+            concurrency: vec![1, 4],
+            duration: 0.2,
+            warm_up: 0.0,
+        };
+        let workloads = Suite::builtin(
+            Vec::new(),
+            vec!["Lookups".to_string(), "Stateful Call".to_string()],
+        );
+        let results =
+            throughput_middleware(arbiter_middleware.clone(), "Arbiter", &workloads, &settings)
+                .await
+                .unwrap();
+        let lookups = &results.workloads[0];
+        assert_eq!(lookups.label, "Arbiter Lookups");
+        let levels: Vec<usize> = lookups.levels.iter().map(|l| l.concurrency).collect();
+        assert_eq!(levels, vec![1, 4]);
+        // concurrent mints get a nonce each
+        for level in lookups.levels.iter().chain(&results.workloads[1].levels) {
+            assert_eq!(level.errors, 0);
+            assert_eq!(level.first_error, None);
+            assert!(level.operations > 0 && level.ops_per_sec > 0.0);
+            assert!(level.latency.p50 <= level.latency.p99);
+        }
+
+        struct Failing;
+
+        #[async_trait::async_trait]
+        impl<M: Middleware + 'static> Workload<M> for Failing {
+            fn name(&self) -> &str {
+                "Failing"
+            }
+
+            async fn setup(&mut self, _client: Arc<M>) -> Result<()> {
+                Ok(())
+            }
+
+            async fn run(&self) -> Result<()> {
+                bail!("rejected")
+            }
+        }
+
+        impl WorkloadFactory for Failing {
+            fn build<M: Middleware + 'static>(&self) -> Result<WorkloadRegistry<M>> {
+                let mut registry = WorkloadRegistry::new();
+                registry.register(Failing);
+                Ok(registry)
+            }
+        }
+
+        let failed = throughput_middleware(arbiter_middleware, "Arbiter", &Failing, &settings)
+            .await
+            .unwrap_err();
+        assert!(failed
+            .to_string()
+            .contains("Arbiter Failing failed with 1 in flight: rejected"));
+        backend.teardown().await.unwrap();
+
+        let invalid = ThroughputSettings {
+            concurrency: vec![0],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn open_loop() {
+        use crate::open_loop::{open_loop_middleware, OpenLoopSettings};
+
+        let mut backend = ArbiterBackend::default();
+        let arbiter_middleware = backend.start().await.unwrap();
+        let settings = OpenLoopSettings {
+            rate: 200.0,
+            duration: 0.25,
+        };
+        let workloads = WorkloadRegistry::builtin()
+            .select(&["Lookups", "Stateful Call"])
+            .unwrap();
+        let results =
+            open_loop_middleware(arbiter_middleware.clone(), "Arbiter", workloads, &settings)
+                .await
+                .unwrap();
+        let lookups = &results.workloads[0];
+        assert_eq!(lookups.label, "Arbiter Lookups");
+        assert!(lookups.corrected.p50 > 0);
+        assert!(lookups.corrected.max >= lookups.uncorrected.max);
+        // overlapping mints get a nonce each
+        for result in &results.workloads {
+            assert_eq!(result.started, 50);
+            assert_eq!(result.completed, 50);
+            assert_eq!(result.errors, 0);
+            assert_eq!(result.first_error, None);
+        }
+
+        struct Failing;
+
+        #[async_trait::async_trait]
+        impl<M: Middleware + 'static> Workload<M> for Failing {
+            fn name(&self) -> &str {
+                "Failing"
+            }
+
+            async fn setup(&mut self, _client: Arc<M>) -> Result<()> {
+                Ok(())
+            }
+
+            async fn run(&self) -> Result<()> {
+                bail!("rejected")
+            }
+        }
+
+        let mut failing = WorkloadRegistry::new();
+        failing.register(Failing);
+        let failed = open_loop_middleware(arbiter_middleware, "Arbiter", failing, &settings)
+            .await
+            .unwrap_err();
+        assert!(failed
+            .to_string()
+            .contains("50 of 50 calls of Arbiter Failing failed at 200/s: rejected"));
+
+        let never = OpenLoopSettings {
+            rate: 1.0,
+            duration: 0.5,
+        };
+        assert!(never.validate().is_err());
+    }
+
+    // Criterion's executor blocks the thread it runs on, which would
+    // starve the HTTP client's connections on a single-threaded runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mock_node() {
+        let mut c = Criterion::default().configure_from_args();
+        let results = bench_backend(
+            &mut c,
+            &mut MockBackend::default(),
+            WorkloadRegistry::builtin(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.backend.name, "Mock");
+        assert_eq!(results.backend.chain_id, Some(MockNode::CHAIN_ID));
+        assert_eq!(
+            results.backend.client_version.as_deref(),
+            Some(MockNode::CLIENT_VERSION)
+        );
+        assert_eq!(results.workloads.len(), 4);
+        assert!(results.get("Stateful Call").unwrap().summary.min > 0.0);
+        // the mock node reports the same gas for everything
+        assert_eq!(results.get("Lookups").unwrap().gas, Some(1_000_000));
+        assert_eq!(results.get("Stateful Call").unwrap().gas, Some(1_000_000));
+
+        // anything the workloads don't need is an error rather than a guess
+        let node = MockNode::spawn().unwrap();
+        let provider = Provider::<Http>::try_from(node.url()).unwrap();
+        assert!(provider.get_block_number().await.is_ok());
+        assert!(provider.get_logs(&Default::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn network_proxy() {
+        use crate::network::{NetworkConditions, Proxy};
+        use std::time::Instant;
+
+        let node = MockNode::spawn().unwrap();
+        let conditions = NetworkConditions {
+            latency_ms: 25.0,
+            jitter_ms: 5.0,
+            ..Default::default()
+        };
+        let proxy = Proxy::spawn(node.url().trim_start_matches("http://"), conditions).unwrap();
+        let provider = Provider::<Http>::try_from(format!("http://{}", proxy.addr())).unwrap();
+        // the first request also pays for the handshake
+        provider.get_block_number().await.unwrap();
+        let start = Instant::now();
+        provider.get_block_number().await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(50), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+        assert_eq!(conditions.to_string(), "25ms +5ms jitter");
+
+        let mut backend = MockBackend::default().through(conditions);
+        let client = backend.start().await.unwrap();
+        let info = backend.info(&client).await;
+        assert_eq!(info.network.as_deref(), Some("25ms +5ms jitter"));
+        backend.teardown().await.unwrap();
+
+        for invalid in [
+            NetworkConditions {
+                latency_ms: -1.0,
+                ..Default::default()
+            },
+            NetworkConditions {
+                drop_rate: 1.0,
+                ..Default::default()
+            },
+            NetworkConditions {
+                bandwidth_kbps: Some(0.0),
+                ..Default::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn instrumented_middleware() {
+        use crate::{
+            instrumented::{calls_middleware, CallReport, CallSettings},
+            suite::Suite,
+        };
+
+        let settings = CallSettings { iterations: 5 };
+        let workloads = Suite::builtin(
+            Vec::new(),
+            vec!["Stateful Call".to_string(), "Lookups".to_string()],
+        );
+        let mut arbiter = ArbiterBackend::default();
+        let arbiter_calls = calls_middleware(
+            arbiter.start().await.unwrap(),
+            "Arbiter",
+            &workloads,
+            &settings,
+        )
+        .await
+        .unwrap();
+        arbiter.teardown().await.unwrap();
+        let stateful = arbiter_calls.get("Stateful Call").unwrap();
+        assert_eq!(stateful.iterations, 5);
+        assert_eq!(stateful.per_iteration("send_transaction"), 1.0);
+        assert_eq!(stateful.per_iteration("call"), 0.0);
+        assert!(stateful.get("send_transaction").unwrap().elapsed_ns > 0.0);
+        let lookups = arbiter_calls.get("Lookups").unwrap();
+        assert_eq!(lookups.per_iteration("call"), 1.0);
+        assert!(lookups.get("send_transaction").is_none());
+
+        // errors are passed on and counted
+        let mut arbiter = ArbiterBackend::default();
+        let client = Arc::new(InstrumentedMiddleware::new(arbiter.start().await.unwrap()));
+        assert!(client
+            .estimate_gas(&Default::default(), None)
+            .await
+            .is_err());
+        assert_eq!(client.get("estimate_gas").unwrap().errors, 1);
+        client.reset();
+        assert!(client.calls().is_empty());
+        arbiter.teardown().await.unwrap();
+
+        // the signer's own lookups and the receipt polling are seen too
+        let mut mock = MockBackend::default();
+        let mock_calls =
+            calls_middleware(mock.start().await.unwrap(), "Mock", &workloads, &settings)
+                .await
+                .unwrap();
+        mock.teardown().await.unwrap();
+        let stateful = mock_calls.get("Stateful Call").unwrap();
+        assert_eq!(stateful.per_iteration("send_raw_transaction"), 1.0);
+        assert!(stateful.get("send_transaction").is_none());
+        for method in [
+            "get_transaction_count",
+            "estimate_gas",
+            "get_transaction_receipt",
+        ] {
+            assert!(stateful.per_iteration(method) >= 1.0, "{}", method);
+        }
+
+        let markdown = CallReport::new(vec![arbiter_calls, mock_calls]).to_markdown();
+        assert!(markdown.contains("\n| Arbiter Stateful Call | send_transaction | 1 | "));
+        assert!(markdown.contains("\n| Mock Lookups | call | 1 | "));
+    }
+
+    // the mock node needs a runtime thread of its own, see `mock_node`
+    #[tokio::test(flavor = "multi_thread")]
+    async fn middleware_stack() {
+        use crate::{stack::StackReport, suite::Suite};
+
+        let mut suite = Suite::builtin(
+            vec!["arbiter".to_string(), "mock".to_string()],
+            vec!["Stateful Call".to_string(), "Lookups".to_string()],
+        );
+        suite.sample_size = Some(10);
+        suite.measurement_time = Some(0.2);
+        suite.warm_up_time = Some(0.1);
+        let mut c = Criterion::default().configure_from_args();
+        let results = suite.stack(&mut c).await.unwrap();
+        assert_eq!(results.len(), 2);
+
+        // Arbiter executes unsigned transactions, so it has no signer layer
+        let layers = |i: usize| -> Vec<&str> {
+            results[i]
+                .layers
+                .iter()
+                .map(|layer| layer.name.as_str())
+                .collect()
+        };
+        assert_eq!(layers(0), vec!["Base", "GasOracle", "NonceManager"]);
+        assert_eq!(
+            layers(1),
+            vec!["Base", "GasOracle", "Signer", "NonceManager"]
+        );
+        let signer = &results[1].layers[2].results;
+        assert_eq!(signer.backend.name, "Mock +Signer");
+        assert_eq!(
+            signer.get("Stateful Call").unwrap().label,
+            "Mock +Signer Stateful Call"
+        );
+        assert!(results[1].marginal_ns("Signer", "Stateful Call").is_some());
+        assert!(results[1].marginal_ns("Base", "Stateful Call").is_none());
+        assert!(results[0].marginal_ns("Signer", "Lookups").is_none());
+
+        let markdown = StackReport::new(results).to_markdown();
+        assert!(markdown.contains("\n| Arbiter Stateful Call | Base | "));
+        assert!(markdown.contains("\n| Mock Lookups | NonceManager | "));
+    }
+
+    #[tokio::test]
+    async fn phases() {
+        use crate::phases::{phases_middleware, PhaseReport, PhaseSettings, PHASES};
+
+        let settings = PhaseSettings {
+            transactions: 5,
+            warm_up: 1,
+        };
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        let arbiter_phases = phases_middleware(client, "Arbiter", false, &settings)
+            .await
+            .unwrap();
+        arbiter.teardown().await.unwrap();
+        // Arbiter signs nothing and executes on submission
+        assert!(arbiter_phases.get("Signing").unwrap().latency.is_none());
+        assert!(arbiter_phases
+            .get("Gas Estimation")
+            .unwrap()
+            .latency
+            .is_none());
+        assert!(arbiter_phases.get("Nonce").unwrap().latency.is_some());
+        // Arbiter can't estimate gas, which fails rather than being skipped
+        let client = arbiter.start().await.unwrap();
+        let unsigned = phases_middleware(client, "Arbiter", true, &settings).await;
+        arbiter.teardown().await.unwrap();
+        assert!(unsigned.is_err());
+        let submission = arbiter_phases.get("Submission").unwrap();
+        assert!(submission.latency.as_ref().unwrap().min > 0.0);
+
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        let mock_phases = phases_middleware(client, "Mock", true, &settings)
+            .await
+            .unwrap();
+        mock.teardown().await.unwrap();
+        assert_eq!(mock_phases.phases.len(), PHASES.len());
+        assert!(mock_phases
+            .phases
+            .iter()
+            .all(|phase| phase.latency.is_some()));
+        let parts: f64 = mock_phases
+            .phases
+            .iter()
+            .map(|phase| phase.latency.as_ref().unwrap().mean)
+            .sum();
+        assert!(parts <= mock_phases.total.mean);
+
+        let markdown = PhaseReport::new(vec![arbiter_phases, mock_phases]).to_markdown();
+        assert!(markdown.starts_with("| Phase | Arbiter | Mock |\n"));
+        assert!(markdown.contains("\n| Signing | - | "));
+        assert!(markdown.contains("\n| **Total** | "));
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        use crate::verify::{diff, observe, VerifyReport, VerifySettings};
+
+        let settings = VerifySettings { mints: 3 };
+        let mut observations = Vec::new();
+        for _ in 0..2 {
+            let mut arbiter = ArbiterBackend::default();
+            let client = arbiter.start().await.unwrap();
+            observations.push(observe(client, "Arbiter", &settings).await.unwrap());
+            arbiter.teardown().await.unwrap();
+        }
+        let arbiter = &observations[0];
+        let wad = U256::from(10_u128.pow(18));
+        assert_eq!(
+            arbiter.get("balance_of($sender)"),
+            Some((wad * 6_u64).to_string().as_str())
+        );
+        assert_eq!(arbiter.get("mint 3: logs"), Some("1"));
+        assert!(arbiter
+            .get("mint 3: log 0")
+            .unwrap()
+            .starts_with("$token topics ["));
+        assert!(diff(arbiter, &observations[1]).is_empty());
+
+        // the mock node executes nothing, so reads come back empty
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        observations.push(observe(client, "Mock", &settings).await.unwrap());
+        mock.teardown().await.unwrap();
+        let differences = diff(&observations[0], &observations[2]);
+        assert!(differences
+            .iter()
+            .any(|difference| difference.key == "balance_of($sender)"));
+
+        let report = VerifyReport::new(observations);
+        assert!(!report.passed());
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("Arbiter matches Arbiter on all "));
+        assert!(markdown.contains("\n```diff\n- deploy token: gas used: "));
+        assert!(markdown.contains(&format!("\n- balance_of($sender): {}\n", wad * 6_u64)));
+    }
+
+    #[tokio::test]
+    async fn reset() {
+        use crate::{
+            bench_functions::{balance_of_slot, TOTAL_SUPPLY_SLOT},
+            snapshot::{Reset, Revertible},
+            utils::deploy_token,
+        };
+
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        let sender = client.default_sender().unwrap();
+        let token = deploy_token(client.clone()).await.unwrap();
+        let snapshots = RevmMiddleware::snapshots(client.clone());
+        let slots = [
+            (token.address(), H256::from_low_u64_be(TOTAL_SUPPLY_SLOT)),
+            (token.address(), balance_of_slot(sender)),
+        ];
+        snapshots.snapshot(&slots).await.unwrap();
+        for _ in 0..2 {
+            for _ in 0..3 {
+                token.mint(sender, 1.into()).send().await.unwrap();
+            }
+            assert_eq!(token.total_supply().call().await.unwrap(), 3.into());
+            snapshots.revert().await.unwrap();
+            assert_eq!(token.balance_of(sender).call().await.unwrap(), 0.into());
+            assert_eq!(token.total_supply().call().await.unwrap(), 0.into());
+        }
+        arbiter.teardown().await.unwrap();
+
+        // every sample mints into an empty balance, so gas is the first mint's
+        let mut c = Criterion::default().configure_from_args();
+        let mut workloads = WorkloadRegistry::builtin()
+            .select(&["Stateful Call"])
+            .unwrap();
+        workloads.defaults(BenchSettings {
+            sample_size: Some(10),
+            reset: Some(Reset::Iteration),
+            ..Default::default()
+        });
+        let results = bench_backend(&mut c, &mut ArbiterBackend::default(), workloads)
+            .await
+            .unwrap();
+        let first_mint = results.get("Stateful Call").unwrap().gas.unwrap();
+        assert!(first_mint > 50_000, "{}", first_mint);
+
+        // resetting a workload that declares no slots would restore nothing
+        let mut workloads = WorkloadRegistry::builtin().select(&["Lookups"]).unwrap();
+        workloads.defaults(BenchSettings {
+            sample_size: Some(10),
+            reset: Some(Reset::Batch),
+            ..Default::default()
+        });
+        let error = bench_backend(&mut c, &mut ArbiterBackend::default(), workloads)
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("Lookups declares none"),
+            "{}",
+            error
+        );
+
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        let snapshots = SignerMiddleware::snapshots(client);
+        snapshots.snapshot(&[]).await.unwrap();
+        snapshots.revert().await.unwrap();
+        snapshots.revert().await.unwrap();
+        mock.teardown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fixture() {
+        use crate::bindings::counter::COUNTER_DEPLOYED_BYTECODE;
+        use crate::fixture::{fixture_account, AccountState, ChainState};
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let holder = fixture_account(0);
+        let counter = fixture_account(1);
+        assert_ne!(holder, counter);
+        let mut state = ChainState::default();
+        state.accounts.insert(
+            holder,
+            AccountState {
+                nonce: 3,
+                balance: U256::exp10(18),
+                ..Default::default()
+            },
+        );
+        state.accounts.insert(
+            counter,
+            AccountState {
+                nonce: 1,
+                code: COUNTER_DEPLOYED_BYTECODE.clone(),
+                storage: [(U256::zero(), U256::from(42))].into(),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains("\"storage\":{\"0x0\":\"0x2a\"}"));
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(json.as_bytes()).unwrap();
+        assert_eq!(
+            ChainState::from_bytes(&gzipped.finish().unwrap()).unwrap(),
+            state
+        );
+        // the rest of what Anvil dumps is ignored
+        let dumped = format!(
+            "{{\"block\":{{\"number\":\"0x5\"}},\"best_block_number\":\"0x5\",{}",
+            &json[1..]
+        );
+        assert_eq!(ChainState::from_bytes(dumped.as_bytes()).unwrap(), state);
+
+        let path =
+            std::env::temp_dir().join(format!("benchlayer-state-{}.json", std::process::id()));
+        state.save(&path).unwrap();
+        let mut arbiter = ArbiterBackend::default().load_state(path.clone());
+        let client = arbiter.start().await.unwrap();
+        assert_eq!(
+            client.get_balance(holder, None).await.unwrap(),
+            U256::exp10(18)
+        );
+        let number = Counter::new(counter, client.clone()).number().call().await;
+        assert_eq!(number.unwrap(), U256::from(42));
+        Counter::new(counter, client.clone())
+            .increment()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            client
+                .get_storage_at(counter, H256::zero(), None)
+                .await
+                .unwrap(),
+            H256::from_low_u64_be(43)
+        );
+        arbiter.teardown().await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn scaling() {
+        use crate::{
+            bench_functions::balance_of_slot,
+            fixture::fixture_account,
+            scaling::{scaling_middleware, ScalingReport, ScalingSettings, SetStorage},
+            utils::deploy_token,
+        };
+
+        let holder = fixture_account(7);
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        let token = deploy_token(client.clone()).await.unwrap();
+        let balance = H256::from_low_u64_be(5);
+        client
+            .set_storage(&[(token.address(), balance_of_slot(holder), balance)])
+            .await
+            .unwrap();
+        assert_eq!(token.balance_of(holder).call().await.unwrap(), 5.into());
+
+        let mut c = Criterion::default().configure_from_args();
+        let settings = ScalingSettings {
+            sizes: vec![10, 100],
+        };
+        let bench = BenchSettings {
+            sample_size: Some(10),
+            ..Default::default()
+        };
+        let arbiter_results = scaling_middleware(&mut c, client, "Arbiter", &settings, &bench)
+            .await
+            .unwrap();
+        arbiter.teardown().await.unwrap();
+        assert_eq!(arbiter_results.sizes.len(), 2);
+        let lookups = arbiter_results.get(100, "Lookups").unwrap();
+        assert_eq!(lookups.label, "Arbiter Lookups");
+        assert!(lookups.summary.mean > 0.0);
+        assert!(arbiter_results.get(10, "Stateful Call").is_some());
+
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        let mock_results = scaling_middleware(&mut c, client, "Mock", &settings, &bench)
+            .await
+            .unwrap();
+        mock.teardown().await.unwrap();
+        assert!(mock_results.get(100, "Stateful Call").is_some());
+
+        let markdown = ScalingReport::new(vec![arbiter_results, mock_results]).to_markdown();
+        assert!(markdown.starts_with("| Benchmark | 10 holders | 100 holders |\n"));
+        assert!(markdown.contains("\n| Mock Stateful Call | "));
+        assert!(ScalingSettings {
+            sizes: vec![100, 10]
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compute() {
+        use crate::{
+            bindings::compute::{Compute, COMPUTE_BYTECODE},
+            compute::{compute_middleware, ComputeReport, ComputeSettings},
+            utils,
+        };
+        use ethers::utils::keccak256;
+
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        utils::ensure_compiled("Compute", &COMPUTE_BYTECODE).unwrap();
+        let contract = Compute::deploy(client.clone(), ())
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        let x = (0..5_u64).fold(1, |x, i| (x * 31 + i) % 1_000_000_007);
+        assert_eq!(
+            contract.arithmetic(5.into()).call().await.unwrap(),
+            x.into()
+        );
+        let h = (0..3).fold([0; 32], |h, _| keccak256(h));
+        assert_eq!(contract.hashing(3.into()).call().await.unwrap(), h);
+        let words = contract.expand_memory(100.into()).call().await.unwrap();
+        assert_eq!(words, 100.into());
+        contract
+            .store(3.into())
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        contract
+            .store(2.into())
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(contract.rounds().call().await.unwrap(), 2.into());
+        assert_eq!(contract.values(0.into()).call().await.unwrap(), 2.into());
+        assert_eq!(contract.values(2.into()).call().await.unwrap(), 1.into());
+
+        let mut c = Criterion::default().configure_from_args();
+        let settings = ComputeSettings {
+            loops: vec![1, 100],
+        };
+        let bench = BenchSettings {
+            sample_size: Some(10),
+            measurement_time: Some(0.2),
+            warm_up_time: Some(0.1),
+            ..Default::default()
+        };
+        let arbiter_results = compute_middleware(&mut c, client, "Arbiter", &settings, &bench)
+            .await
+            .unwrap();
+        arbiter.teardown().await.unwrap();
+        let hashing = arbiter_results.get(100, "Hashing").unwrap();
+        assert_eq!(hashing.label, "Arbiter Hashing");
+        assert!(hashing.summary.mean > 0.0);
+        // Arbiter reports gas from receipts only
+        let storage = |n| arbiter_results.get(n, "Storage").unwrap().gas.unwrap();
+        assert!(storage(100) > storage(1) + 99 * 2_900);
+        assert!(arbiter_results.fit("Arithmetic").is_some());
+
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        let mock_results = compute_middleware(&mut c, client, "Mock", &settings, &bench)
+            .await
+            .unwrap();
+        mock.teardown().await.unwrap();
+        assert!(mock_results.get(1, "Memory").is_some());
+
+        let markdown = ComputeReport::new(vec![arbiter_results, mock_results]).to_markdown();
+        assert!(markdown.starts_with("| Benchmark | 1 loops | 100 loops | Per call | Per loop |\n"));
+        assert!(markdown.contains("\n| Mock Storage | "));
+        assert!(ComputeSettings { loops: vec![10] }.validate().is_err());
+    }
+
+    // the mock node needs a runtime thread of its own, see `mock_node`
+    #[tokio::test(flavor = "multi_thread")]
+    async fn precompiles() {
+        use crate::{
+            bindings::precompiles::{Precompiles, PRECOMPILES_BYTECODE},
+            precompiles::Precompile,
+            suite::Suite,
+            utils,
+            workload::PRECOMPILE_WORKLOADS,
+        };
+        use ethers::{types::Bytes, utils::hex};
+
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        utils::ensure_compiled("Precompiles", &PRECOMPILES_BYTECODE).unwrap();
+        let contract = Precompiles::deploy(client.clone(), ())
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        let mut outputs = Vec::new();
+        for precompile in Precompile::ALL {
+            let run = contract.run(precompile.address(), precompile.input());
+            outputs.push(run.call().await.unwrap());
+        }
+        // in order of address, starting from 1
+        let output = |precompile: Precompile| -> &Bytes {
+            &outputs[precompile.address().to_low_u64_be() as usize - 1]
+        };
+        let signer = LocalWallet::from_bytes(&[1; 32]).unwrap().address();
+        assert_eq!(output(Precompile::Ecrecover)[12..], signer.0);
+        let sha256 = "40aff2e9d2d8922e47afd4648e6967497158785fbd1da870e7110266bf944880";
+        assert_eq!(hex::encode(output(Precompile::Sha256)), sha256);
+        let ripemd160 = "9c4fa072db2c871a5635e37f791e93ab45049676";
+        assert_eq!(hex::encode(&output(Precompile::Ripemd160)[12..]), ripemd160);
+        assert_eq!(output(Precompile::Identity), &Precompile::Identity.input());
+        assert_eq!(output(Precompile::Modexp).len(), 32);
+        let doubled = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+                       15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+        assert_eq!(hex::encode(output(Precompile::Bn256Add)), doubled);
+        assert_eq!(output(Precompile::Bn256Mul).len(), 64);
+        assert_eq!(U256::from(&output(Precompile::Bn256Pairing)[..]), 1.into());
+        let blake2b = "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                       7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923";
+        assert_eq!(hex::encode(output(Precompile::Blake2f)), blake2b);
+        arbiter.teardown().await.unwrap();
+
+        let settings = BenchSettings {
+            sample_size: Some(10),
+            measurement_time: Some(0.2),
+            warm_up_time: Some(0.1),
+            ..Default::default()
+        };
+        let mut c = Criterion::default().configure_from_args();
+        let mut workloads = WorkloadRegistry::precompiles();
+        workloads.defaults(settings.clone());
+        let arbiter_results = bench_backend(&mut c, &mut ArbiterBackend::default(), workloads)
+            .await
+            .unwrap();
+        assert_eq!(arbiter_results.workloads.len(), PRECOMPILE_WORKLOADS.len());
+        let pairing = arbiter_results.get("Bn256 Pairing").unwrap();
+        assert_eq!(pairing.label, "Arbiter Bn256 Pairing");
+        assert!(pairing.summary.mean > 0.0);
+        let mut workloads = WorkloadRegistry::precompiles();
+        workloads.defaults(settings);
+        let mock_results = bench_backend(&mut c, &mut MockBackend::default(), workloads)
+            .await
+            .unwrap();
+        assert_eq!(mock_results.workloads.len(), PRECOMPILE_WORKLOADS.len());
+
+        let suite = Suite::load("suites/precompiles.toml").unwrap();
+        let names = WorkloadRegistry::<RevmMiddleware>::precompiles();
+        assert_eq!(names.names(), suite.workloads);
+        assert_eq!(names.names(), PRECOMPILE_WORKLOADS);
+    }
+
+    // the mock node needs a runtime thread of its own, see `mock_node`
+    #[tokio::test(flavor = "multi_thread")]
+    async fn depth() {
+        use crate::{
+            bindings::relay::{Relay, RELAY_BYTECODE},
+            depth::{depth_middleware, DepthReport, DepthSettings},
+            utils,
+        };
+
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        utils::ensure_compiled("Relay", &RELAY_BYTECODE).unwrap();
+        let mut chain: Vec<Relay<RevmMiddleware>> = Vec::new();
+        for _ in 0..3 {
+            let next = chain
+                .last()
+                .map_or(Address::zero(), |relay| relay.address());
+            let relay = Relay::deploy(client.clone(), next)
+                .unwrap()
+                .send()
+                .await
+                .unwrap();
+            chain.push(relay);
+        }
+        let (last, first) = (&chain[0], &chain[2]);
+        assert_eq!(first.next().call().await.unwrap(), chain[1].address());
+        assert_eq!(first.relay_call(2.into()).call().await.unwrap(), 1.into());
+        first
+            .relay_call(2.into())
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        // a call counts the hit in the last link, a delegatecall in the first
+        assert_eq!(last.hits().call().await.unwrap(), 1.into());
+        assert_eq!(first.hits().call().await.unwrap(), 0.into());
+        first
+            .relay_delegatecall(2.into())
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(first.hits().call().await.unwrap(), 1.into());
+        assert_eq!(last.hits().call().await.unwrap(), 1.into());
+        first
+            .relay_call(2.into())
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        let hits = first.relay_staticcall(2.into()).call().await.unwrap();
+        assert_eq!(hits, 2.into());
+        assert!(first.relay_staticcall(3.into()).call().await.is_err());
+
+        let mut c = Criterion::default().configure_from_args();
+        let settings = DepthSettings { depths: vec![0, 4] };
+        let bench = BenchSettings {
+            sample_size: Some(10),
+            measurement_time: Some(0.2),
+            warm_up_time: Some(0.1),
+            ..Default::default()
+        };
+        let arbiter_results = depth_middleware(&mut c, client, "Arbiter", &settings, &bench)
+            .await
+            .unwrap();
+        arbiter.teardown().await.unwrap();
+        let delegatecall = arbiter_results.get(4, "Delegatecall").unwrap();
+        assert_eq!(delegatecall.label, "Arbiter Delegatecall");
+        assert!(delegatecall.summary.mean > 0.0);
+        // Arbiter can't estimate gas for calls
+        assert_eq!(delegatecall.gas, None);
+
+        let mut mock = MockBackend::default();
+        let client = mock.start().await.unwrap();
+        let mock_results = depth_middleware(&mut c, client, "Mock", &settings, &bench)
+            .await
+            .unwrap();
+        mock.teardown().await.unwrap();
+        assert_eq!(
+            mock_results.get(0, "Staticcall").unwrap().gas,
+            Some(1_000_000)
+        );
+
+        let markdown = DepthReport::new(vec![arbiter_results, mock_results]).to_markdown();
+        assert!(markdown.starts_with("| Benchmark | depth 0 | depth 4 |\n"));
+        assert!(markdown.contains("\n| Mock Call | "));
+        assert!(DepthSettings { depths: vec![4, 2] }.validate().is_err());
+        assert!(DepthSettings { depths: vec![1024] }.validate().is_err());
+    }
+
+    // the mock node needs a runtime thread of its own, see `mock_node`
+    #[tokio::test(flavor = "multi_thread")]
+    async fn transfer_from() {
+        use crate::suite::Suite;
+
+        let mut c = Criterion::default().configure_from_args();
+        let bench = BenchSettings {
+            sample_size: Some(10),
+            measurement_time: Some(0.2),
+            warm_up_time: Some(0.1),
+            ..Default::default()
+        };
+
+        // the owner approves and a spender of its own sends the transfers
+        let mut arbiter = ArbiterBackend::default();
+        let client = arbiter.start().await.unwrap();
+        let spender = arbiter.account(&client, "spender").await.unwrap();
+        assert_ne!(spender.address(), client.address());
+        let mut workloads = WorkloadRegistry::transfers(spender);
+        workloads.defaults(bench);
+        let results = bench_middleware(&mut c, client.clone(), "Arbiter", workloads)
+            .await
+            .unwrap();
+        // two approvals and two transfers
+        assert!(results.get("Transfer From").unwrap().gas.unwrap() > 4 * 21_000);
+        let error = bench_middleware(
+            &mut c,
+            client.clone(),
+            "Arbiter",
+            WorkloadRegistry::transfers(client),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("other than the owner's"),
+            "{}",
+            error
+        );
+        arbiter.teardown().await.unwrap();
+
+        // suites set the spender up for the transfer workloads
+        let mut suite = Suite::builtin(vec!["mock".to_string()], vec!["Transfer From".to_string()]);
+        suite.sample_size = Some(10);
+        suite.measurement_time = Some(0.2);
+        suite.warm_up_time = Some(0.1);
+        let results = suite.run(&mut c).await.unwrap();
+        assert_eq!(
+            results[0].get("Transfer From").unwrap().gas,
+            Some(4_000_000)
+        );
+        assert!(suite.throughput(&Default::default()).await.is_err());
+        assert!(WorkloadRegistry::<RevmMiddleware>::builtin()
+            .select(&["Transfer From"])
+            .is_err());
+    }
 }
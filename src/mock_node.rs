@@ -0,0 +1,330 @@
+//! An in-process stand-in for a JSON-RPC node.
+//!
+//! [`MockNode`] serves HTTP JSON-RPC on a local port and answers the methods
+//! the workloads use with canned responses. Nothing is executed: calls return
+//! a zero word, every transaction is mined straight away and deployments get
+//! an address from the sender's nonce. Benchmarking against it measures what
+//! the provider, signer and serialization cost on their own.
+//!
+//! Like a dev node, it also takes unsigned transactions through
+//! `eth_sendTransaction` and lists the account of [`MockNode::KEY`] in
+//! `eth_accounts`, so a bare provider can send transactions too.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, Block, Bytes, FeeHistory, Signature, Transaction, TransactionReceipt, H256, U256,
+        U64,
+    },
+    utils::{
+        get_contract_address, keccak256,
+        rlp::{Rlp, RlpStream},
+    },
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::utils::ServerThread;
+
+/// Gas estimated for every call and transaction.
+const GAS: u64 = 1_000_000;
+/// Base fee of every block, in wei.
+const BASE_FEE: u64 = 1_000_000_000;
+
+/// A running mock node, stopped when dropped.
+pub struct MockNode {
+    server: ServerThread,
+}
+
+impl MockNode {
+    pub const CHAIN_ID: u64 = 31337;
+    pub const CLIENT_VERSION: &'static str = "BenchLayer/mock";
+    /// The first Anvil dev key. The node accepts transactions signed by any
+    /// key, this is just one to hand to a client.
+    pub const KEY: &'static str =
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// Starts serving on a free local port.
+    pub fn spawn() -> Result<Self> {
+        Ok(Self {
+            server: ServerThread::spawn(serve)?,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.server.addr())
+    }
+}
+
+struct Chain {
+    /// The account of [`MockNode::KEY`].
+    account: Address,
+    block: u64,
+    nonces: HashMap<Address, u64>,
+    transactions: HashMap<H256, (Transaction, TransactionReceipt)>,
+}
+
+async fn serve(listener: TcpListener) {
+    let chain = Arc::new(Mutex::new(Chain {
+        account: MockNode::KEY.parse::<LocalWallet>().unwrap().address(),
+        block: 0,
+        nonces: HashMap::new(),
+        transactions: HashMap::new(),
+    }));
+    while let Result::Ok((stream, _)) = listener.accept().await {
+        let chain = chain.clone();
+        tokio::spawn(async move {
+            // a dropped connection just ends the task
+            let _ = connection(stream, &chain).await;
+        });
+    }
+}
+
+/// Answers requests on a keep-alive HTTP/1.1 connection until it's closed.
+async fn connection(stream: TcpStream, chain: &Mutex<Chain>) -> Result<()> {
+    // otherwise small responses wait out the client's delayed ACK
+    stream.set_nodelay(true)?;
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        let mut length = 0;
+        line.clear();
+        if stream.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        loop {
+            line.clear();
+            stream.read_line(&mut line).await?;
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse()?;
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).await?;
+
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Result::Ok(Value::Array(batch)) => {
+                Value::Array(batch.iter().map(|call| respond(call, chain)).collect())
+            }
+            Result::Ok(call) => respond(&call, chain),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": err.to_string() },
+            }),
+        };
+        let body = serde_json::to_vec(&response)?;
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&body);
+        stream.get_mut().write_all(&response).await?;
+    }
+}
+
+fn respond(call: &Value, chain: &Mutex<Chain>) -> Value {
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+    let method = call.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = call.get("params").cloned().unwrap_or(Value::Null);
+    match answer(method, &params, &mut chain.lock().unwrap()) {
+        Result::Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": err.to_string() },
+        }),
+    }
+}
+
+fn answer(method: &str, params: &Value, chain: &mut Chain) -> Result<Value> {
+    let result = match method {
+        "eth_chainId" => json!(U64::from(MockNode::CHAIN_ID)),
+        "net_version" => json!(MockNode::CHAIN_ID.to_string()),
+        "web3_clientVersion" => json!(MockNode::CLIENT_VERSION),
+        "eth_blockNumber" => json!(U64::from(chain.block)),
+        "eth_gasPrice" => json!(U256::from(2 * BASE_FEE)),
+        "eth_maxPriorityFeePerGas" => json!(U256::from(BASE_FEE)),
+        "eth_estimateGas" => json!(U256::from(GAS)),
+        "eth_getBalance" => json!(U256::MAX),
+        "eth_accounts" => json!([chain.account]),
+        "eth_call" => json!(Bytes::from(vec![0; 32])),
+        "eth_getCode" => json!(Bytes::new()),
+        // there's no state to save
+        "evm_snapshot" => json!(U256::one()),
+        "evm_revert" => json!(true),
+        "anvil_setStorageAt" => json!(true),
+        "eth_getTransactionCount" => {
+            let sender: Address = param(params, 0)?;
+            json!(U256::from(chain.nonces.get(&sender).copied().unwrap_or(0)))
+        }
+        "eth_getBlockByNumber" => json!(Block::<H256> {
+            number: Some(chain.block.into()),
+            base_fee_per_gas: Some(BASE_FEE.into()),
+            ..Default::default()
+        }),
+        "eth_feeHistory" => json!(FeeHistory {
+            base_fee_per_gas: vec![BASE_FEE.into(); 2],
+            gas_used_ratio: vec![0.5],
+            oldest_block: chain.block.into(),
+            reward: vec![vec![BASE_FEE.into()]],
+        }),
+        "eth_sendRawTransaction" => {
+            let raw: Bytes = param(params, 0)?;
+            json!(mine(chain, Signed::decode(&raw)?, keccak256(&raw).into()))
+        }
+        "eth_sendTransaction" => {
+            let request: Request = param(params, 0)?;
+            let nonce = match request.nonce {
+                Some(nonce) => nonce,
+                None => chain.nonces.get(&request.from).copied().unwrap_or(0).into(),
+            };
+            // unique per sender and nonce, like the hash of a signed one
+            let mut preimage = [0; 52];
+            preimage[..20].copy_from_slice(request.from.as_bytes());
+            nonce.to_big_endian(&mut preimage[20..]);
+            let signed = Signed {
+                from: request.from,
+                to: request.to,
+                nonce,
+            };
+            json!(mine(chain, signed, keccak256(preimage).into()))
+        }
+        "eth_getTransactionByHash" => {
+            let hash: H256 = param(params, 0)?;
+            json!(chain.transactions.get(&hash).map(|(tx, _)| tx))
+        }
+        "eth_getTransactionReceipt" => {
+            let hash: H256 = param(params, 0)?;
+            json!(chain.transactions.get(&hash).map(|(_, receipt)| receipt))
+        }
+        _ => return Err(anyhow!("the mock node doesn't support {}", method)),
+    };
+    Ok(result)
+}
+
+fn param<T: serde::de::DeserializeOwned>(params: &Value, index: usize) -> Result<T> {
+    let param = params
+        .get(index)
+        .ok_or_else(|| anyhow!("missing parameter {}", index))?;
+    Ok(serde_json::from_value(param.clone())?)
+}
+
+/// Includes a transaction in a block of its own.
+fn mine(chain: &mut Chain, signed: Signed, hash: H256) -> H256 {
+    let nonce = chain.nonces.entry(signed.from).or_default();
+    let contract_address = signed
+        .to
+        .is_none()
+        .then(|| get_contract_address(signed.from, *nonce));
+    *nonce += 1;
+    chain.block += 1;
+
+    let tx = Transaction {
+        hash,
+        nonce: signed.nonce,
+        block_hash: Some(H256::from_low_u64_be(chain.block)),
+        block_number: Some(chain.block.into()),
+        transaction_index: Some(0.into()),
+        from: signed.from,
+        to: signed.to,
+        ..Default::default()
+    };
+    let receipt = TransactionReceipt {
+        transaction_hash: tx.hash,
+        transaction_index: 0.into(),
+        block_hash: tx.block_hash,
+        block_number: tx.block_number,
+        from: tx.from,
+        to: tx.to,
+        cumulative_gas_used: GAS.into(),
+        gas_used: Some(GAS.into()),
+        contract_address,
+        status: Some(1.into()),
+        effective_gas_price: Some((2 * BASE_FEE).into()),
+        ..Default::default()
+    };
+    chain.transactions.insert(hash, (tx, receipt));
+    hash
+}
+
+/// The parts of an `eth_sendTransaction` request the mock node needs.
+#[derive(Deserialize)]
+struct Request {
+    from: Address,
+    to: Option<Address>,
+    nonce: Option<U256>,
+}
+
+/// The parts of a signed transaction the mock node needs.
+struct Signed {
+    from: Address,
+    to: Option<Address>,
+    nonce: U256,
+}
+
+impl Signed {
+    /// Decodes a legacy or typed transaction by hand, since with the `celo`
+    /// feature ethers signs legacy transactions with extra fee fields that it
+    /// can't decode again. Every type ends in the signature, with the
+    /// recipient three fields before the data.
+    fn decode(raw: &[u8]) -> Result<Self> {
+        let (kind, fields) = match raw.first() {
+            Some(&kind) if kind <= 0x7f => (Some(kind), Rlp::new(&raw[1..])),
+            _ => (None, Rlp::new(raw)),
+        };
+        let count = fields.item_count()?;
+        if count < 9 {
+            bail!("not a signed transaction");
+        }
+        let signature = Signature {
+            v: fields.val_at(count - 3)?,
+            r: fields.val_at(count - 2)?,
+            s: fields.val_at(count - 1)?,
+        };
+
+        let unsigned = count - 3;
+        // EIP-155 legacy transactions sign over the chain id too
+        let chain_id = match kind {
+            None if signature.v >= 35 => Some((signature.v - 35) / 2),
+            _ => None,
+        };
+        let mut payload = RlpStream::new_list(unsigned + if chain_id.is_some() { 3 } else { 0 });
+        for index in 0..unsigned {
+            payload.append_raw(fields.at(index)?.as_raw(), 1);
+        }
+        if let Some(chain_id) = chain_id {
+            payload.append(&chain_id).append(&0u8).append(&0u8);
+        }
+        let mut preimage = kind.map(|kind| vec![kind]).unwrap_or_default();
+        preimage.extend_from_slice(&payload.out());
+
+        let to = fields.at(unsigned - if kind.is_some() { 4 } else { 3 })?;
+        Ok(Self {
+            from: signature.recover(H256::from(keccak256(preimage)))?,
+            to: if to.is_empty() {
+                None
+            } else {
+                Some(to.as_val()?)
+            },
+            nonce: fields.val_at(if kind.is_some() { 1 } else { 0 })?,
+        })
+    }
+}
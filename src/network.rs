@@ -0,0 +1,188 @@
+//! Simulated network conditions.
+//!
+//! Local backends answer over loopback, which hides what a remote node costs.
+//! A [`Proxy`] sits between the provider and the node and delays whatever
+//! passes through it by a [`NetworkConditions`], so the same workloads can be
+//! measured as if the node were far away.
+
+use anyhow::{bail, Ok, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    sync::mpsc,
+    time::{sleep, sleep_until, Instant},
+};
+
+use std::{fmt, net::SocketAddr, time::Duration};
+
+use crate::utils::ServerThread;
+
+/// How long a lost segment takes to be resent, Linux's minimum TCP
+/// retransmission timeout.
+const RETRANSMISSION_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Applied in each direction, so a request and its response together see
+/// twice the latency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConditions {
+    /// Milliseconds added to everything sent.
+    #[serde(default)]
+    pub latency_ms: f64,
+    /// Up to this many milliseconds more, picked uniformly at random.
+    #[serde(default)]
+    pub jitter_ms: f64,
+    /// Kilobits per second each way. Unlimited when unset.
+    pub bandwidth_kbps: Option<f64>,
+    /// Fraction of writes lost and resent after a retransmission timeout.
+    /// TCP hides the loss itself, this is the stall it causes.
+    #[serde(default)]
+    pub drop_rate: f64,
+}
+
+impl NetworkConditions {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.latency_ms >= 0.0 && self.latency_ms.is_finite()) {
+            bail!("latency must be a non-negative number of milliseconds");
+        }
+        if !(self.jitter_ms >= 0.0 && self.jitter_ms.is_finite()) {
+            bail!("jitter must be a non-negative number of milliseconds");
+        }
+        if let Some(bandwidth) = self.bandwidth_kbps {
+            if !(bandwidth > 0.0 && bandwidth.is_finite()) {
+                bail!("bandwidth must be a positive number of kilobits per second");
+            }
+        }
+        if !(0.0..1.0).contains(&self.drop_rate) {
+            bail!("drop rate must be at least 0 and less than 1");
+        }
+        Ok(())
+    }
+
+    /// How long `bytes` occupy the link for.
+    fn transmission(&self, bytes: usize) -> Duration {
+        match self.bandwidth_kbps {
+            Some(kbps) => Duration::from_secs_f64(bytes as f64 * 8.0 / (kbps * 1000.0)),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// How long a write takes to arrive once it's on the link.
+    fn delay(&self, rng: &mut StdRng) -> Duration {
+        let mut delay = self.latency_ms + rng.gen::<f64>() * self.jitter_ms;
+        if rng.gen::<f64>() < self.drop_rate {
+            delay += RETRANSMISSION_TIMEOUT.as_secs_f64() * 1000.0;
+        }
+        Duration::from_secs_f64(delay / 1000.0)
+    }
+}
+
+impl fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![format!("{}ms", self.latency_ms)];
+        if self.jitter_ms > 0.0 {
+            parts[0].push_str(&format!(" +{}ms jitter", self.jitter_ms));
+        }
+        if let Some(bandwidth) = self.bandwidth_kbps {
+            parts.push(format!("{} kbit/s", bandwidth));
+        }
+        if self.drop_rate > 0.0 {
+            parts.push(format!("{}% dropped", self.drop_rate * 100.0));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// A TCP proxy to `upstream` that applies [`NetworkConditions`] both ways.
+/// Stopped when dropped.
+pub struct Proxy {
+    server: ServerThread,
+}
+
+impl Proxy {
+    /// Starts listening on a free local port.
+    pub fn spawn(upstream: impl Into<String>, conditions: NetworkConditions) -> Result<Self> {
+        conditions.validate()?;
+        let upstream = upstream.into();
+        let server = ServerThread::spawn(move |listener| serve(listener, upstream, conditions))?;
+        Ok(Self { server })
+    }
+
+    /// Where to connect instead of the upstream.
+    pub fn addr(&self) -> SocketAddr {
+        self.server.addr()
+    }
+}
+
+async fn serve(listener: TcpListener, upstream: String, conditions: NetworkConditions) {
+    while let Result::Ok((client, _)) = listener.accept().await {
+        let upstream = upstream.clone();
+        tokio::spawn(async move {
+            // a failed connection just ends the task, the client sees it closed
+            let _ = connection(client, &upstream, conditions).await;
+        });
+    }
+}
+
+async fn connection(
+    client: TcpStream,
+    upstream: &str,
+    conditions: NetworkConditions,
+) -> Result<()> {
+    // the handshake costs a round trip before anything is sent
+    sleep(conditions.delay(&mut StdRng::from_entropy()) * 2).await;
+    let server = TcpStream::connect(upstream).await?;
+    client.set_nodelay(true)?;
+    server.set_nodelay(true)?;
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+    tokio::try_join!(
+        link(client_read, server_write, conditions),
+        link(server_read, client_write, conditions),
+    )?;
+    Ok(())
+}
+
+/// Forwards one direction of a connection, delivering each write once it has
+/// crossed the simulated link.
+async fn link(
+    mut from: OwnedReadHalf,
+    mut to: OwnedWriteHalf,
+    conditions: NetworkConditions,
+) -> Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+    let read = async move {
+        let mut rng = StdRng::from_entropy();
+        let mut buffer = vec![0; 64 * 1024];
+        // when the link is done transmitting what it already has
+        let mut idle = Instant::now();
+        let mut last = Instant::now();
+        loop {
+            let read = from.read(&mut buffer).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            idle = idle.max(Instant::now()) + conditions.transmission(read);
+            // TCP delivers in order, so nothing overtakes an earlier write
+            last = last.max(idle + conditions.delay(&mut rng));
+            if sender.send((last, buffer[..read].to_vec())).is_err() {
+                return Ok(());
+            }
+        }
+    };
+    let write = async move {
+        while let Some((arrival, bytes)) = receiver.recv().await {
+            sleep_until(arrival).await;
+            to.write_all(&bytes).await?;
+        }
+        to.shutdown().await?;
+        Ok(())
+    };
+    tokio::try_join!(read, write)?;
+    Ok(())
+}
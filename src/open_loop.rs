@@ -0,0 +1,287 @@
+//! Open-loop load at a constant rate.
+//!
+//! Calls are started on a fixed schedule whether or not earlier ones have
+//! returned, so a backend that falls behind builds up a queue instead of
+//! quietly slowing the load down. Latency is measured from when each call was
+//! scheduled to start, which corrects for coordinated omission: a stall delays
+//! every call queued behind it, and those delays are counted. The latency
+//! from when each call actually started is kept too, for comparison.
+//!
+//! Overlapping calls go through a nonce manager, as in [`crate::throughput`],
+//! and a workload where more than [`MAX_ERROR_RATE`] of the calls fail fails
+//! the run, since failed calls aren't in the latencies.
+
+use anyhow::{bail, Ok, Result};
+use ethers::providers::Middleware;
+use futures::{stream::FuturesUnordered, StreamExt};
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::BackendInfo,
+    throughput::{nonce_managed, NonceManaged, MAX_ERROR_RATE},
+    workload::{Workload, WorkloadRegistry},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenLoopSettings {
+    /// Calls started per second.
+    pub rate: f64,
+    /// Seconds to keep starting calls for.
+    pub duration: f64,
+}
+
+impl Default for OpenLoopSettings {
+    fn default() -> Self {
+        Self {
+            rate: 100.0,
+            duration: 10.0,
+        }
+    }
+}
+
+impl OpenLoopSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.rate > 0.0 && self.rate.is_finite()) {
+            bail!("rate must be a positive number of calls per second");
+        }
+        if !(self.duration > 0.0 && self.duration.is_finite()) {
+            bail!("duration must be a positive number of seconds");
+        }
+        if self.calls() == 0 {
+            bail!(
+                "a rate of {}/s never starts a call in {}s",
+                self.rate,
+                self.duration
+            );
+        }
+        Ok(())
+    }
+
+    fn calls(&self) -> u64 {
+        (self.rate * self.duration) as u64
+    }
+}
+
+/// Percentiles read off an HDR histogram, in nanoseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Latencies {
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl Latencies {
+    fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        Self {
+            mean: histogram.mean(),
+            p50: histogram.value_at_quantile(0.5),
+            p90: histogram.value_at_quantile(0.9),
+            p99: histogram.value_at_quantile(0.99),
+            p999: histogram.value_at_quantile(0.999),
+            max: histogram.max(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenLoopResult {
+    /// The backend and workload together, e.g. "Anvil Stateful Call".
+    pub label: String,
+    pub workload: String,
+    pub target_rate: f64,
+    /// Calls completed per second, from the first call starting to the last
+    /// one returning. Falls short of the target once the backend saturates.
+    pub achieved_rate: f64,
+    pub started: u64,
+    pub completed: u64,
+    /// Calls that returned an error, at most [`MAX_ERROR_RATE`] of them.
+    /// They aren't counted in the rate or the latencies.
+    pub errors: u64,
+    /// What one of the failed calls returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+    /// Latency from each call's scheduled start.
+    pub corrected: Latencies,
+    /// Latency from each call's actual start, which hides queueing.
+    pub uncorrected: Latencies,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenLoopResults {
+    pub backend: BackendInfo,
+    pub workloads: Vec<OpenLoopResult>,
+}
+
+/// Runs every workload at the rate in `settings` in turn, on `client` behind
+/// a nonce manager. Workloads sending from other accounts need theirs behind
+/// one too, see [`nonce_managed`].
+pub async fn open_loop_middleware<M: Middleware + 'static>(
+    client: Arc<M>,
+    label: &str,
+    mut workloads: WorkloadRegistry<NonceManaged<M>>,
+    settings: &OpenLoopSettings,
+) -> Result<OpenLoopResults> {
+    settings.validate()?;
+    let mut results = OpenLoopResults {
+        backend: BackendInfo::query(label, &*client).await,
+        workloads: Vec::new(),
+    };
+    let client = nonce_managed(client)?;
+    for (workload, _) in workloads.iter_mut() {
+        workload.setup(client.clone()).await?;
+        let name = workload.name().to_string();
+        println!(
+            "{} {}: {}/s for {}s",
+            label, name, settings.rate, settings.duration
+        );
+        let result = drive(&**workload, format!("{} {}", label, name), settings).await?;
+        println!(
+            "    {:.1}/s achieved, p99 {} corrected, {} uncorrected, {} errors",
+            result.achieved_rate,
+            format_ns(result.corrected.p99 as f64),
+            format_ns(result.uncorrected.p99 as f64),
+            result.errors
+        );
+        if result.errors as f64 > MAX_ERROR_RATE * result.started as f64 {
+            bail!(
+                "{} of {} calls of {} failed at {}/s: {}",
+                result.errors,
+                result.started,
+                result.label,
+                settings.rate,
+                result.first_error.as_deref().unwrap_or_default()
+            );
+        }
+        workload.teardown().await?;
+        results.workloads.push(result);
+    }
+    Ok(results)
+}
+
+async fn drive<M: Middleware + 'static>(
+    workload: &dyn Workload<M>,
+    label: String,
+    settings: &OpenLoopSettings,
+) -> Result<OpenLoopResult> {
+    // 1ns to an hour at three significant figures, clamping anything longer
+    let highest = Duration::from_secs(3600).as_nanos() as u64;
+    let mut corrected = Histogram::<u64>::new_with_bounds(1, highest, 3)?;
+    let mut uncorrected = corrected.clone();
+    let calls = settings.calls();
+
+    let call = |scheduled: Instant| async move {
+        let started = Instant::now();
+        let outcome = workload.run().await;
+        let finished = Instant::now();
+        (outcome, finished - scheduled, finished - started)
+    };
+
+    let start = Instant::now();
+    let mut in_flight = FuturesUnordered::new();
+    let mut started = 0;
+    let (mut completed, mut errors) = (0, 0);
+    let mut first_error = None;
+    loop {
+        let next = start + Duration::from_secs_f64(started as f64 / settings.rate);
+        let done = if started < calls {
+            tokio::select! {
+                _ = tokio::time::sleep_until(next.into()) => {
+                    in_flight.push(call(next));
+                    started += 1;
+                    continue;
+                }
+                Some(done) = in_flight.next() => done,
+            }
+        } else {
+            match in_flight.next().await {
+                Some(done) => done,
+                None => break,
+            }
+        };
+        match done {
+            (Result::Ok(()), from_schedule, from_start) => {
+                completed += 1;
+                corrected.saturating_record(from_schedule.as_nanos() as u64);
+                uncorrected.saturating_record(from_start.as_nanos() as u64);
+            }
+            (Err(err), _, _) => {
+                errors += 1;
+                first_error.get_or_insert_with(|| format!("{:#}", err));
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    Ok(OpenLoopResult {
+        label,
+        workload: workload.name().to_string(),
+        target_rate: settings.rate,
+        achieved_rate: completed as f64 / elapsed.as_secs_f64(),
+        started,
+        completed,
+        errors,
+        first_error,
+        corrected: Latencies::from_histogram(&corrected),
+        uncorrected: Latencies::from_histogram(&uncorrected),
+    })
+}
+
+/// The open-loop results of a run, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenLoopReport {
+    pub schema_version: u32,
+    pub results: Vec<OpenLoopResults>,
+}
+
+impl OpenLoopReport {
+    pub fn new(results: Vec<OpenLoopResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from(
+            "| Benchmark | Target/s | Achieved/s | p50 | p99 | p99.9 | Max | p99 uncorrected | Errors |\n\
+             | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: |\n",
+        );
+        for result in self.results.iter().flat_map(|results| &results.workloads) {
+            writeln!(
+                md,
+                "| {} | {:.1} | {:.1} | {} | {} | {} | {} | {} | {} |",
+                result.label,
+                result.target_rate,
+                result.achieved_rate,
+                format_ns(result.corrected.p50 as f64),
+                format_ns(result.corrected.p99 as f64),
+                format_ns(result.corrected.p999 as f64),
+                format_ns(result.corrected.max as f64),
+                format_ns(result.uncorrected.p99 as f64),
+                result.errors
+            )
+            .unwrap();
+        }
+        md
+    }
+}
@@ -0,0 +1,252 @@
+//! Where the time in a stateful transaction goes.
+//!
+//! `Stateful Call` times `mint(...).send().await?.await?` as a whole. Here the
+//! same mint is taken apart into the steps a client goes through, each timed
+//! on its own: looking up the nonce and gas price, estimating gas, signing,
+//! submitting and waiting for the receipt.
+//!
+//! A client without a signer of its own hands the transaction over unsigned,
+//! and the backend estimates gas and signs it as part of submission, as
+//! Arbiter does. Whoever runs the phases says up front whether the client
+//! signs, and the two steps are reported as skipped when it doesn't. Any
+//! other step failing fails the run.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use arbiter_core::bindings::arbiter_token::ArbiterToken;
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::Write,
+    future::Future,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, Summary},
+    utils,
+};
+
+/// The steps of a transaction, in the order they happen.
+pub const PHASES: &[&str] = &[
+    "Nonce",
+    "Gas Price",
+    "Gas Estimation",
+    "Signing",
+    "Submission",
+    "Confirmation",
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhaseSettings {
+    /// Transactions to time.
+    pub transactions: usize,
+    /// Transactions to send before timing any.
+    pub warm_up: usize,
+}
+
+impl Default for PhaseSettings {
+    fn default() -> Self {
+        Self {
+            transactions: 100,
+            warm_up: 10,
+        }
+    }
+}
+
+impl PhaseSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.transactions == 0 {
+            bail!("at least one transaction is needed");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Phase {
+    pub name: String,
+    /// Latency in nanoseconds, or `None` if the backend never did this step
+    /// on its own.
+    pub latency: Option<Summary>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhaseResults {
+    pub backend: BackendInfo,
+    /// One entry per name in [`PHASES`], in order.
+    pub phases: Vec<Phase>,
+    /// Latency of the whole transaction in nanoseconds.
+    pub total: Summary,
+}
+
+impl PhaseResults {
+    /// The result for a phase by name, e.g. "Signing".
+    pub fn get(&self, phase: &str) -> Option<&Phase> {
+        self.phases.iter().find(|p| p.name == phase)
+    }
+}
+
+/// Mints tokens phase by phase, as many times as `settings` asks. `signs`
+/// says whether `client` estimates gas and signs transactions itself, like a
+/// `SignerMiddleware`.
+pub async fn phases_middleware<M: Middleware + 'static>(
+    client: Arc<M>,
+    label: &str,
+    signs: bool,
+    settings: &PhaseSettings,
+) -> Result<PhaseResults> {
+    settings.validate()?;
+    let backend = BackendInfo::query(label, &*client).await;
+    let token = utils::deploy_token(client.clone()).await?;
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| anyhow!("client has no default sender"))?;
+    println!(
+        "{}: timing the phases of {} mints",
+        label, settings.transactions
+    );
+
+    for _ in 0..settings.warm_up {
+        mint(&*client, &token, sender, backend.chain_id, signs).await?;
+    }
+    let mut times = vec![Vec::new(); PHASES.len()];
+    let mut totals = Vec::with_capacity(settings.transactions);
+    for _ in 0..settings.transactions {
+        let start = Instant::now();
+        let phases = mint(&*client, &token, sender, backend.chain_id, signs).await?;
+        totals.push(start.elapsed().as_nanos() as f64);
+        for (phase, time) in phases.iter().enumerate() {
+            if let Some(time) = time {
+                times[phase].push(time.as_nanos() as f64);
+            }
+        }
+    }
+
+    Ok(PhaseResults {
+        backend,
+        phases: PHASES
+            .iter()
+            .zip(times)
+            .map(|(name, times)| Phase {
+                name: name.to_string(),
+                latency: (!times.is_empty()).then(|| Summary::from_times(&times)),
+            })
+            .collect(),
+        total: Summary::from_times(&totals),
+    })
+}
+
+/// Sends one mint, returning how long each of [`PHASES`] took. Unless the
+/// client `signs`, gas estimation and signing are left for the backend to do
+/// on submission.
+async fn mint<M: Middleware + 'static>(
+    client: &M,
+    token: &ArbiterToken<M>,
+    sender: Address,
+    chain_id: Option<u64>,
+    signs: bool,
+) -> Result<Vec<Option<Duration>>> {
+    let mut phases = vec![None; PHASES.len()];
+    let mut tx = token.mint(sender, U256::from(10_u128.pow(18))).tx;
+    tx.set_from(sender);
+    if let Some(chain_id) = chain_id {
+        tx.set_chain_id(chain_id);
+    }
+
+    let (nonce, time) = timed(client.get_transaction_count(sender, None)).await;
+    tx.set_nonce(nonce?);
+    phases[0] = Some(time);
+    let (gas_price, time) = timed(client.get_gas_price()).await;
+    tx.set_gas_price(gas_price?);
+    phases[1] = Some(time);
+    let signed = if signs {
+        let (gas, time) = timed(client.estimate_gas(&tx, None)).await;
+        tx.set_gas(gas?);
+        phases[2] = Some(time);
+        let (signature, time) = timed(client.sign_transaction(&tx, sender)).await;
+        phases[3] = Some(time);
+        Some(tx.rlp_signed(&signature?))
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    let pending = match signed {
+        Some(raw) => client.send_raw_transaction(raw).await?,
+        None => client.send_transaction(tx, None).await?,
+    };
+    phases[4] = Some(start.elapsed());
+    let (receipt, time) = timed(pending).await;
+    receipt?.ok_or_else(|| anyhow!("mint was dropped before it was mined"))?;
+    phases[5] = Some(time);
+    Ok(phases)
+}
+
+async fn timed<T>(future: impl Future<Output = T>) -> (T, Duration) {
+    let start = Instant::now();
+    let output = future.await;
+    (output, start.elapsed())
+}
+
+/// The phase breakdowns of a run, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhaseReport {
+    pub schema_version: u32,
+    pub results: Vec<PhaseResults>,
+}
+
+impl PhaseReport {
+    pub fn new(results: Vec<PhaseResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// Mean latencies with a column per backend. Skipped phases are shown
+    /// as `-`.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("| Phase |");
+        for results in &self.results {
+            write!(md, " {} |", results.backend.name).unwrap();
+        }
+        md.push_str("\n| --- |");
+        md.push_str(&" ---: |".repeat(self.results.len()));
+        md.push('\n');
+        let cell = |summary: &Summary| {
+            format!(
+                " {} ± {} |",
+                format_ns(summary.mean),
+                format_ns(summary.std_dev)
+            )
+        };
+        for (index, name) in PHASES.iter().enumerate() {
+            write!(md, "| {} |", name).unwrap();
+            for results in &self.results {
+                match results.phases.get(index).and_then(|p| p.latency.as_ref()) {
+                    Some(summary) => md.push_str(&cell(summary)),
+                    None => md.push_str(" - |"),
+                }
+            }
+            md.push('\n');
+        }
+        md.push_str("| **Total** |");
+        for results in &self.results {
+            md.push_str(&cell(&results.total));
+        }
+        md.push('\n');
+        md
+    }
+}
@@ -0,0 +1,196 @@
+//! Workloads calling each of the EVM precompiles.
+//!
+//! Backends implement precompiles natively and in different ways, so a
+//! signature check or a pairing costs differently on each. Every workload
+//! deploys the `Precompiles` contract in `contracts/precompiles.sol` and
+//! calls one precompile through it with the same valid input each time.
+
+use anyhow::{Ok, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    signers::LocalWallet,
+    types::{Address, Bytes, H256, U256},
+    utils::{hex, keccak256},
+};
+
+use std::sync::Arc;
+
+use crate::{
+    bench_functions::deployed,
+    bindings::precompiles::{Precompiles, PRECOMPILES_BYTECODE},
+    utils,
+    workload::Workload,
+};
+
+/// Bytes hashed by sha256 and ripemd160, and copied by identity.
+const DATA_LEN: usize = 256;
+
+/// Order of the field bn256 points are over.
+const BN256_FIELD: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+/// The generator of bn256's G2, as `x` and `y` with their imaginary parts
+/// first.
+const BN256_G2: [&str; 4] = [
+    "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+    "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+    "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+    "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+];
+
+/// 12 rounds compressing the last block of blake2b("abc"), test vector 5 of
+/// EIP-152.
+const BLAKE2F_INPUT: &str = "0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3a\
+    f54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precompile {
+    Ecrecover,
+    Sha256,
+    Ripemd160,
+    Identity,
+    Modexp,
+    Bn256Add,
+    Bn256Mul,
+    Bn256Pairing,
+    Blake2f,
+}
+
+impl Precompile {
+    /// In order of address.
+    pub const ALL: [Precompile; 9] = [
+        Precompile::Ecrecover,
+        Precompile::Sha256,
+        Precompile::Ripemd160,
+        Precompile::Identity,
+        Precompile::Modexp,
+        Precompile::Bn256Add,
+        Precompile::Bn256Mul,
+        Precompile::Bn256Pairing,
+        Precompile::Blake2f,
+    ];
+
+    /// The name its workload is benchmarked under, one of
+    /// [`crate::workload::PRECOMPILE_WORKLOADS`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Precompile::Ecrecover => "Ecrecover",
+            Precompile::Sha256 => "Sha256",
+            Precompile::Ripemd160 => "Ripemd160",
+            Precompile::Identity => "Identity",
+            Precompile::Modexp => "Modexp",
+            Precompile::Bn256Add => "Bn256 Add",
+            Precompile::Bn256Mul => "Bn256 Mul",
+            Precompile::Bn256Pairing => "Bn256 Pairing",
+            Precompile::Blake2f => "Blake2f",
+        }
+    }
+
+    pub fn address(self) -> Address {
+        let index = Self::ALL.iter().position(|p| *p == self).unwrap();
+        Address::from_low_u64_be(index as u64 + 1)
+    }
+
+    /// The input its workload calls it with.
+    pub fn input(self) -> Bytes {
+        match self {
+            Precompile::Ecrecover => {
+                let hash = H256::from(keccak256("benchlayer"));
+                // a valid key, ecrecover doesn't care whose it is
+                let wallet = LocalWallet::from_bytes(&[1; 32]).unwrap();
+                let signature = wallet.sign_hash(hash).unwrap();
+                let mut input = hash.as_bytes().to_vec();
+                input.extend(words(&[signature.v.into(), signature.r, signature.s]));
+                input.into()
+            }
+            Precompile::Sha256 | Precompile::Ripemd160 | Precompile::Identity => {
+                (0..DATA_LEN).map(|i| i as u8).collect::<Vec<_>>().into()
+            }
+            // 256 bit base, exponent and modulus, the modulus odd
+            Precompile::Modexp => {
+                let word = |seed: &str| U256::from(keccak256(seed));
+                words(&[
+                    32.into(),
+                    32.into(),
+                    32.into(),
+                    word("base"),
+                    word("exponent"),
+                    word("modulus") | U256::one(),
+                ])
+                .into()
+            }
+            // the generator added to itself
+            Precompile::Bn256Add => words(&[1.into(), 2.into(), 1.into(), 2.into()]).into(),
+            Precompile::Bn256Mul => {
+                let scalar = U256::from(keccak256("scalar"));
+                words(&[1.into(), 2.into(), scalar]).into()
+            }
+            // e(g1, g2) * e(-g1, g2), which is 1
+            Precompile::Bn256Pairing => {
+                let field = U256::from_str_radix(BN256_FIELD, 16).unwrap();
+                let g2 = BN256_G2.map(|word| U256::from_str_radix(word, 16).unwrap());
+                let mut pairs = vec![1.into(), 2.into()];
+                pairs.extend(g2);
+                pairs.extend([1.into(), field - 2]);
+                pairs.extend(g2);
+                words(&pairs).into()
+            }
+            Precompile::Blake2f => hex::decode(BLAKE2F_INPUT).unwrap().into(),
+        }
+    }
+}
+
+/// Each value as a big-endian 32-byte word.
+fn words(values: &[U256]) -> Vec<u8> {
+    let mut bytes = vec![0; values.len() * 32];
+    for (value, word) in values.iter().zip(bytes.chunks_mut(32)) {
+        value.to_big_endian(word);
+    }
+    bytes
+}
+
+/// Calls one precompile through the `Precompiles` contract.
+pub struct PrecompileCall<M: Middleware + 'static> {
+    precompile: Precompile,
+    input: Bytes,
+    contract: Option<Precompiles<M>>,
+}
+
+impl<M: Middleware + 'static> PrecompileCall<M> {
+    pub fn new(precompile: Precompile) -> Self {
+        Self {
+            precompile,
+            input: precompile.input(),
+            contract: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Workload<M> for PrecompileCall<M> {
+    fn name(&self) -> &str {
+        self.precompile.name()
+    }
+
+    async fn setup(&mut self, client: Arc<M>) -> Result<()> {
+        utils::ensure_compiled("Precompiles", &PRECOMPILES_BYTECODE)?;
+        self.contract = Some(Precompiles::deploy(client, ())?.send().await?);
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        let contract = deployed(&self.contract)?;
+        let address = self.precompile.address();
+        contract.run(address, self.input.clone()).call().await?;
+        Ok(())
+    }
+
+    async fn gas(&self) -> Result<Option<u64>> {
+        let contract = deployed(&self.contract)?;
+        let run = contract.run(self.precompile.address(), self.input.clone());
+        Ok(Some(run.estimate_gas().await?.as_u64()))
+    }
+}
@@ -0,0 +1,202 @@
+//! Gating a run on a saved baseline.
+//!
+//! A baseline is a [`Report`] saved under a name. Later runs are checked
+//! against it label by label, e.g. "Arbiter Stateful Call", and a workload
+//! regresses when it got slower by more than a [`Threshold`].
+
+use anyhow::{anyhow, bail, Ok, Result};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    compare::{bootstrap_ratio, mean, per_iteration, CompareOptions},
+    report::{format_ns, Report},
+    results::WorkloadResult,
+};
+
+/// Where the baseline called `name` is saved in `dir`.
+pub fn baseline_path(dir: impl AsRef<Path>, name: &str) -> PathBuf {
+    dir.as_ref().join(format!("{}.json", name))
+}
+
+/// How much slower a workload may get before it counts as a regression.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Threshold {
+    /// The mean time grew by more than this many percent.
+    Percent(f64),
+    /// The bootstrapped confidence interval of the slowdown lies entirely
+    /// above 1 at this confidence level.
+    Significant(f64),
+}
+
+impl FromStr for Threshold {
+    type Err = anyhow::Error;
+
+    /// Parses `5%`, `significant` or `significant:0.99`.
+    fn from_str(raw: &str) -> Result<Self> {
+        let threshold = if let Some(percent) = raw.strip_suffix('%') {
+            Threshold::Percent(percent.trim().parse()?)
+        } else if raw == "significant" {
+            Threshold::Significant(CompareOptions::default().confidence)
+        } else if let Some(confidence) = raw.strip_prefix("significant:") {
+            Threshold::Significant(confidence.parse()?)
+        } else {
+            bail!(
+                "invalid threshold {:?}, expected e.g. `5%`, `significant` or `significant:0.99`",
+                raw
+            );
+        };
+        match threshold {
+            Threshold::Percent(percent) if !(percent >= 0.0 && percent.is_finite()) => Err(
+                anyhow!("threshold percentage must be a non-negative number"),
+            ),
+            Threshold::Significant(confidence) if !(confidence > 0.0 && confidence < 1.0) => {
+                Err(anyhow!("confidence must be between 0 and 1"))
+            }
+            _ => Ok(threshold),
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Threshold::Percent(percent) => write!(f, "{}%", percent),
+            Threshold::Significant(confidence) => write!(f, "significant:{}", confidence),
+        }
+    }
+}
+
+/// One label checked against the baseline.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+    pub label: String,
+    /// Mean time per iteration in the baseline, in nanoseconds.
+    pub baseline_mean: f64,
+    /// Mean time per iteration in this run, in nanoseconds.
+    pub mean: f64,
+    /// Percentage change of the mean, positive when slower.
+    pub change: f64,
+    pub regressed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gate {
+    pub threshold: Threshold,
+    pub checks: Vec<Check>,
+    /// Labels in the run that the baseline has no results for.
+    pub unmatched: Vec<String>,
+    /// Labels in the baseline that the run has no results for, which fail
+    /// the gate since they can't be checked.
+    pub missing: Vec<String>,
+}
+
+impl Gate {
+    /// Checks every label in `current` that `baseline` also has, and notes
+    /// the ones only either of them has.
+    pub fn new(baseline: &Report, current: &Report, threshold: Threshold) -> Self {
+        let mut rng = StdRng::seed_from_u64(CompareOptions::default().seed);
+        let mut checks = Vec::new();
+        let mut unmatched = Vec::new();
+        for result in current
+            .results
+            .iter()
+            .flat_map(|results| &results.workloads)
+        {
+            match find(baseline, &result.label) {
+                Some(old) => checks.push(check(old, result, threshold, &mut rng)),
+                None => unmatched.push(result.label.clone()),
+            }
+        }
+        let missing = baseline
+            .results
+            .iter()
+            .flat_map(|results| &results.workloads)
+            .filter(|result| find(current, &result.label).is_none())
+            .map(|result| result.label.clone())
+            .collect();
+        Self {
+            threshold,
+            checks,
+            unmatched,
+            missing,
+        }
+    }
+
+    pub fn regressions(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| check.regressed)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.regressions().next().is_none() && self.missing.is_empty()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("| Benchmark | Baseline | Now | Change | |\n");
+        md.push_str("| --- | ---: | ---: | ---: | --- |\n");
+        for check in &self.checks {
+            writeln!(
+                md,
+                "| {} | {} | {} | {:+.2}% | {} |",
+                check.label,
+                format_ns(check.baseline_mean),
+                format_ns(check.mean),
+                check.change,
+                if check.regressed { "regressed" } else { "ok" }
+            )
+            .unwrap();
+        }
+        for label in &self.missing {
+            writeln!(md, "| {} | - | - | - | missing |", label).unwrap();
+        }
+        md
+    }
+}
+
+fn find<'a>(report: &'a Report, label: &str) -> Option<&'a WorkloadResult> {
+    report
+        .results
+        .iter()
+        .flat_map(|results| &results.workloads)
+        .find(|result| result.label == label)
+}
+
+fn check(
+    old: &WorkloadResult,
+    new: &WorkloadResult,
+    threshold: Threshold,
+    rng: &mut StdRng,
+) -> Check {
+    let old_times = per_iteration(&old.samples);
+    let new_times = per_iteration(&new.samples);
+    let (baseline_mean, mean) = (mean(&old_times), mean(&new_times));
+    let change = (mean / baseline_mean - 1.0) * 100.0;
+    let regressed = match threshold {
+        Threshold::Percent(percent) => change > percent,
+        // a single sample has no spread to test against
+        Threshold::Significant(_) if old_times.len() < 2 || new_times.len() < 2 => false,
+        Threshold::Significant(confidence) => {
+            let (lower, _) = bootstrap_ratio(
+                &new_times,
+                &old_times,
+                CompareOptions::default().resamples,
+                confidence,
+                rng,
+            );
+            lower > 1.0
+        }
+    };
+    Check {
+        label: new.label.clone(),
+        baseline_mean,
+        mean,
+        change,
+        regressed,
+    }
+}
@@ -0,0 +1,340 @@
+//! Exporting [`BenchResults`] once a run has finished.
+//!
+//! A [`Report`] collects the results of every backend in a run and renders
+//! them as versioned JSON, as CSV with one row per backend and workload, or as
+//! Markdown tables for pasting into PRs. Workloads that report gas are also
+//! checked for backends disagreeing on it.
+
+use anyhow::{bail, Context, Ok, Result};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, fs, path::Path};
+
+use crate::results::{BenchResults, WorkloadResult};
+
+/// Bumped whenever the JSON layout changes in a way old readers can't handle.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const CSV_HEADER: &[&str] = &[
+    "backend",
+    "workload",
+    "label",
+    "transport",
+    "network",
+    "chain_id",
+    "client_version",
+    "samples",
+    "iterations",
+    "mean_ns",
+    "median_ns",
+    "std_dev_ns",
+    "min_ns",
+    "max_ns",
+    "p50_ns",
+    "p90_ns",
+    "p95_ns",
+    "p99_ns",
+    "gas",
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    /// One entry per backend, in the order they were benchmarked.
+    pub results: Vec<BenchResults>,
+}
+
+impl From<Vec<BenchResults>> for Report {
+    fn from(results: Vec<BenchResults>) -> Self {
+        Self::new(results)
+    }
+}
+
+impl Report {
+    pub fn new(results: Vec<BenchResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// The backend names, in the order they were benchmarked.
+    pub fn backends(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .map(|results| results.backend.name.as_str())
+            .collect()
+    }
+
+    /// Every workload run against any backend, in the order first seen.
+    pub fn workloads(&self) -> Vec<&str> {
+        let mut workloads: Vec<&str> = Vec::new();
+        for result in self.results.iter().flat_map(|results| &results.workloads) {
+            if !workloads.contains(&result.workload.as_str()) {
+                workloads.push(&result.workload);
+            }
+        }
+        workloads
+    }
+
+    pub fn get(&self, backend: &str, workload: &str) -> Option<&WorkloadResult> {
+        self.results
+            .iter()
+            .find(|results| results.backend.name == backend)
+            .and_then(|results| results.get(workload))
+    }
+
+    /// Workloads whose gas differs between backends, out of those that
+    /// reported it.
+    pub fn gas_mismatches(&self) -> Vec<&str> {
+        self.workloads()
+            .into_iter()
+            .filter(|workload| {
+                let mut gas = self
+                    .results
+                    .iter()
+                    .filter_map(|results| results.get(workload)?.gas);
+                gas.next()
+                    .is_some_and(|first| gas.any(|other| other != first))
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a report written by [`Report::to_json`], refusing other schema
+    /// versions.
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(raw)?;
+        match value.get("schema_version").and_then(|v| v.as_u64()) {
+            Some(version) if version == SCHEMA_VERSION as u64 => {}
+            Some(version) => bail!(
+                "unsupported schema version {}, expected {}",
+                version,
+                SCHEMA_VERSION
+            ),
+            None => bail!("missing schema_version"),
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read results {}", path.display()))?;
+        Self::from_json(&raw).with_context(|| format!("invalid results {}", path.display()))
+    }
+
+    /// Writes the report in the format given by the file extension: `.json`,
+    /// `.csv` or `.md`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let rendered = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => self.to_json()?,
+            Some("csv") => self.to_csv(),
+            Some("md") => self.to_markdown(),
+            _ => bail!(
+                "can't tell the format of {}, use .json, .csv or .md",
+                path.display()
+            ),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, rendered).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// One row per backend and workload, times in nanoseconds.
+    pub fn to_csv(&self) -> String {
+        let mut csv = CSV_HEADER.join(",");
+        csv.push('\n');
+        for results in &self.results {
+            let backend = &results.backend;
+            for result in &results.workloads {
+                let summary = &result.summary;
+                let row = [
+                    csv_field(&backend.name),
+                    csv_field(&result.workload),
+                    csv_field(&result.label),
+                    csv_field(backend.transport.as_deref().unwrap_or_default()),
+                    csv_field(backend.network.as_deref().unwrap_or_default()),
+                    backend
+                        .chain_id
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                    csv_field(backend.client_version.as_deref().unwrap_or_default()),
+                    result.samples.len().to_string(),
+                    result.iterations.to_string(),
+                    summary.mean.to_string(),
+                    summary.median.to_string(),
+                    summary.std_dev.to_string(),
+                    summary.min.to_string(),
+                    summary.max.to_string(),
+                    summary.p50.to_string(),
+                    summary.p90.to_string(),
+                    summary.p95.to_string(),
+                    summary.p99.to_string(),
+                    result.gas.map(|gas| gas.to_string()).unwrap_or_default(),
+                ];
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+
+    /// A table comparing backends side by side for each workload, then one of
+    /// their gas if any was reported, followed by a table per backend.
+    pub fn to_markdown(&self) -> String {
+        let backends = self.backends();
+        let mut md = String::new();
+
+        md.push_str("## By workload\n\n| Workload |");
+        for backend in &backends {
+            write!(md, " {} |", backend).unwrap();
+        }
+        md.push_str("\n| --- |");
+        md.push_str(&" ---: |".repeat(backends.len()));
+        md.push('\n');
+        for workload in self.workloads() {
+            write!(md, "| {} |", workload).unwrap();
+            for backend in &backends {
+                match self.get(backend, workload) {
+                    Some(result) => write!(
+                        md,
+                        " {} ± {} |",
+                        format_ns(result.summary.mean),
+                        format_ns(result.summary.std_dev)
+                    )
+                    .unwrap(),
+                    None => md.push_str(" - |"),
+                }
+            }
+            md.push('\n');
+        }
+
+        let gas_reported = self
+            .results
+            .iter()
+            .flat_map(|results| &results.workloads)
+            .any(|result| result.gas.is_some());
+        if gas_reported {
+            md.push_str("\n## Gas\n\n| Workload |");
+            for backend in &backends {
+                write!(md, " {} |", backend).unwrap();
+            }
+            md.push_str("\n| --- |");
+            md.push_str(&" ---: |".repeat(backends.len()));
+            md.push('\n');
+            for workload in self.workloads() {
+                write!(md, "| {} |", workload).unwrap();
+                for backend in &backends {
+                    match self.get(backend, workload).and_then(|result| result.gas) {
+                        Some(gas) => write!(md, " {} |", gas).unwrap(),
+                        None => md.push_str(" - |"),
+                    }
+                }
+                md.push('\n');
+            }
+            let mismatches = self.gas_mismatches();
+            if !mismatches.is_empty() {
+                writeln!(
+                    md,
+                    "\n**Gas differs between backends for {}.**",
+                    mismatches.join(", ")
+                )
+                .unwrap();
+            }
+        }
+
+        md.push_str("\n## By backend\n");
+        for results in &self.results {
+            let backend = &results.backend;
+            write!(md, "\n### {}\n\n", backend.name).unwrap();
+            if let Some(transport) = &backend.transport {
+                writeln!(md, "- Transport: {}", transport).unwrap();
+            }
+            if let Some(network) = &backend.network {
+                writeln!(md, "- Network: {}", network).unwrap();
+            }
+            if let Some(version) = &backend.client_version {
+                writeln!(md, "- Client: `{}`", version).unwrap();
+            }
+            if let Some(chain_id) = backend.chain_id {
+                writeln!(md, "- Chain id: {}", chain_id).unwrap();
+            }
+            if backend.transport.is_some()
+                || backend.network.is_some()
+                || backend.client_version.is_some()
+                || backend.chain_id.is_some()
+            {
+                md.push('\n');
+            }
+            md.push_str("| Workload | Mean | Median | Std dev | p95 | p99 | Samples | Gas |\n");
+            md.push_str("| --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: |\n");
+            for result in &results.workloads {
+                let summary = &result.summary;
+                writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                    result.workload,
+                    format_ns(summary.mean),
+                    format_ns(summary.median),
+                    format_ns(summary.std_dev),
+                    format_ns(summary.p95),
+                    format_ns(summary.p99),
+                    result.samples.len(),
+                    result
+                        .gas
+                        .map_or_else(|| "-".to_string(), |gas| gas.to_string())
+                )
+                .unwrap();
+            }
+        }
+        md
+    }
+}
+
+/// Writes `value` as JSON or `markdown` as Markdown, going by the extension of
+/// `path`, for reports that have no CSV form.
+pub(crate) fn save_json_or_markdown<T: Serialize>(
+    path: &Path,
+    value: &T,
+    markdown: impl FnOnce() -> String,
+) -> Result<()> {
+    let rendered = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string_pretty(value)?,
+        Some("md") => markdown(),
+        _ => bail!(
+            "can't tell the format of {}, use .json or .md",
+            path.display()
+        ),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, rendered).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Formats a duration in nanoseconds with a readable unit.
+pub fn format_ns(ns: f64) -> String {
+    if ns < 1e3 {
+        format!("{:.2} ns", ns)
+    } else if ns < 1e6 {
+        format!("{:.2} µs", ns / 1e3)
+    } else if ns < 1e9 {
+        format!("{:.2} ms", ns / 1e6)
+    } else {
+        format!("{:.2} s", ns / 1e9)
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
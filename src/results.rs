@@ -0,0 +1,129 @@
+use ethers::providers::Middleware;
+use serde::{Deserialize, Serialize};
+
+/// What was benchmarked, as far as the client can tell.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackendInfo {
+    /// The label passed to [`crate::bench_middleware`], e.g. "Arbiter".
+    pub name: String,
+    /// How the client reaches the EVM, e.g. "in-process" or "http".
+    pub transport: Option<String>,
+    /// The simulated network conditions, if any.
+    pub network: Option<String>,
+    pub chain_id: Option<u64>,
+    /// As reported by `web3_clientVersion`.
+    pub client_version: Option<String>,
+}
+
+impl BackendInfo {
+    /// Asks the client for its metadata. Backends that don't support a query
+    /// leave the field unset rather than failing the run.
+    pub async fn query<M: Middleware>(name: &str, client: &M) -> Self {
+        Self {
+            name: name.to_string(),
+            transport: None,
+            network: None,
+            chain_id: client.get_chainid().await.ok().map(|id| id.as_u64()),
+            client_version: client.client_version().await.ok(),
+        }
+    }
+}
+
+/// Everything measured by one call to [`crate::bench_middleware`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchResults {
+    pub backend: BackendInfo,
+    pub workloads: Vec<WorkloadResult>,
+}
+
+impl BenchResults {
+    /// The result for a workload by name, e.g. "Lookups".
+    pub fn get(&self, workload: &str) -> Option<&WorkloadResult> {
+        self.workloads
+            .iter()
+            .find(|result| result.workload == workload)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadResult {
+    /// The backend and workload together, e.g. "Arbiter Stateful Call".
+    pub label: String,
+    pub workload: String,
+    /// The samples Criterion measured, warm-up excluded.
+    pub samples: Vec<Sample>,
+    /// Total iterations across `samples`.
+    pub iterations: u64,
+    pub summary: Summary,
+    /// Gas used by one iteration, if the workload and backend report it.
+    pub gas: Option<u64>,
+}
+
+/// A batch of iterations timed together.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub iterations: u64,
+    pub elapsed_ns: f64,
+}
+
+impl Sample {
+    pub fn per_iteration_ns(&self) -> f64 {
+        self.elapsed_ns / self.iterations as f64
+    }
+}
+
+/// Statistics over the per-iteration time of each sample, in nanoseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Summary {
+    pub fn from_samples(samples: &[Sample]) -> Self {
+        let times: Vec<f64> = samples.iter().map(Sample::per_iteration_ns).collect();
+        Self::from_times(&times)
+    }
+
+    /// Summarises per-iteration times. Percentiles interpolate between the
+    /// closest ranks, and the standard deviation is the sample one.
+    pub fn from_times(times: &[f64]) -> Self {
+        if times.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = times.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let std_dev = if sorted.len() > 1 {
+            (sorted.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        Self {
+            mean,
+            median: percentile(&sorted, 50.0),
+            std_dev,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+        }
+    }
+}
+
+/// The `p`th percentile of already sorted, non-empty `sorted`.
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
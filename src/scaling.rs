@@ -0,0 +1,249 @@
+//! How backends slow down as their state grows.
+//!
+//! A token is deployed and given more and more holders, written straight
+//! into its `balanceOf` mapping rather than minted so that a million of them
+//! can be set up in reasonable time. At each size `lookup` and
+//! `stateful_call` are benchmarked again with Criterion, parameterized by the
+//! number of holders, so Criterion plots their latency against state size.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use arbiter_core::{environment::cheatcodes::Cheatcodes, middleware::RevmMiddleware};
+use async_trait::async_trait;
+use criterion::{BenchmarkId, Criterion};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{JsonRpcClient, Middleware, Provider},
+    signers::LocalWallet,
+    types::{Address, H256, U256},
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, path::Path, sync::Arc, time::Instant};
+
+use crate::{
+    bench_functions::{balance_of_slot, lookup, stateful_call},
+    fixture::fixture_account,
+    record,
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, WorkloadResult},
+    utils,
+    workload::BenchSettings,
+};
+
+/// The workloads rerun at each state size.
+pub const SCALING_WORKLOADS: &[&str] = &["Lookups", "Stateful Call"];
+
+/// Holders written per batch, to bound memory at the largest sizes.
+const WRITE_BATCH: usize = 10_000;
+/// Writes in flight at once over RPC.
+const WRITES_IN_FLIGHT: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScalingSettings {
+    /// Numbers of token holders to benchmark at, in increasing order.
+    pub sizes: Vec<usize>,
+}
+
+impl Default for ScalingSettings {
+    fn default() -> Self {
+        Self {
+            sizes: vec![1_000, 10_000, 100_000, 1_000_000],
+        }
+    }
+}
+
+impl ScalingSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.sizes.is_empty() {
+            bail!("at least one state size is needed");
+        }
+        if self.sizes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            bail!("state sizes must be increasing");
+        }
+        Ok(())
+    }
+}
+
+/// A backend's middleware whose storage can be written directly.
+#[async_trait]
+pub trait SetStorage: Middleware + 'static {
+    /// Writes each value into a contract's storage slot without executing
+    /// anything.
+    async fn set_storage(&self, writes: &[(Address, H256, H256)]) -> Result<()>;
+}
+
+#[async_trait]
+impl SetStorage for RevmMiddleware {
+    async fn set_storage(&self, writes: &[(Address, H256, H256)]) -> Result<()> {
+        for &(account, key, value) in writes {
+            let store = Cheatcodes::Store {
+                account,
+                key,
+                value,
+            };
+            self.apply_cheatcode(store).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + 'static> SetStorage for SignerMiddleware<Provider<T>, LocalWallet> {
+    async fn set_storage(&self, writes: &[(Address, H256, H256)]) -> Result<()> {
+        let provider = self.provider();
+        stream::iter(writes.iter().copied())
+            .map(|(account, key, value)| async move {
+                let slot = U256::from_big_endian(key.as_bytes());
+                let set: bool = provider
+                    .request("anvil_setStorageAt", (account, slot, value))
+                    .await?;
+                if !set {
+                    bail!("the node refused to set storage of {:?}", account);
+                }
+                Ok(())
+            })
+            .buffer_unordered(WRITES_IN_FLIGHT)
+            .try_collect()
+            .await
+    }
+}
+
+/// The workloads benchmarked with a given number of token holders.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SizeResults {
+    pub size: usize,
+    pub workloads: Vec<WorkloadResult>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScalingResults {
+    pub backend: BackendInfo,
+    /// In increasing order of size.
+    pub sizes: Vec<SizeResults>,
+}
+
+impl ScalingResults {
+    pub fn get(&self, size: usize, workload: &str) -> Option<&WorkloadResult> {
+        self.sizes
+            .iter()
+            .find(|results| results.size == size)?
+            .workloads
+            .iter()
+            .find(|result| result.workload == workload)
+    }
+}
+
+/// Benchmarks [`SCALING_WORKLOADS`] on `client` at each of the sizes in
+/// `settings`, under the Criterion group "`label` State Size".
+pub async fn scaling_middleware<M: SetStorage>(
+    c: &mut Criterion,
+    client: Arc<M>,
+    label: &str,
+    settings: &ScalingSettings,
+    bench: &BenchSettings,
+) -> Result<ScalingResults> {
+    settings.validate()?;
+    let backend = BackendInfo::query(label, &*client).await;
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| anyhow!("client has no default sender"))?;
+    let token = utils::deploy_token(client.clone()).await?;
+    let mut balance = H256::zero();
+    U256::exp10(18).to_big_endian(balance.as_bytes_mut());
+
+    let mut group = c.benchmark_group(format!("{} State Size", label));
+    bench.apply(&mut group);
+    let mut holders = 0;
+    let mut sizes = Vec::with_capacity(settings.sizes.len());
+    for &size in &settings.sizes {
+        while holders < size {
+            let batch = (size - holders).min(WRITE_BATCH);
+            let writes: Vec<_> = (holders..holders + batch)
+                .map(|index| {
+                    let slot = balance_of_slot(fixture_account(index));
+                    (token.address(), slot, balance)
+                })
+                .collect();
+            client.set_storage(&writes).await?;
+            holders += batch;
+        }
+        println!("{}: benchmarking with {} token holders", label, size);
+
+        let mut workloads = Vec::with_capacity(SCALING_WORKLOADS.len());
+        for &name in SCALING_WORKLOADS {
+            let id = Some(BenchmarkId::new(name, size));
+            let token = &token;
+            workloads.extend(record(
+                &mut group,
+                id,
+                label,
+                name,
+                bench,
+                |iters| async move {
+                    let start = Instant::now();
+                    for _ in 0..iters {
+                        match name {
+                            "Lookups" => lookup(token.clone()).await?,
+                            _ => stateful_call(token.clone(), sender).await?,
+                        }
+                    }
+                    Ok(start.elapsed())
+                },
+            )?);
+        }
+        sizes.push(SizeResults { size, workloads });
+    }
+    group.finish();
+    Ok(ScalingResults { backend, sizes })
+}
+
+/// The results of a state size sweep, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScalingReport {
+    pub schema_version: u32,
+    pub results: Vec<ScalingResults>,
+}
+
+impl ScalingReport {
+    pub fn new(results: Vec<ScalingResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A row per backend and workload, with the mean time at each size.
+    pub fn to_markdown(&self) -> String {
+        let Some(first) = self.results.first() else {
+            return String::new();
+        };
+        let sizes: Vec<usize> = first.sizes.iter().map(|results| results.size).collect();
+        let mut md = String::from("| Benchmark |");
+        for size in &sizes {
+            write!(md, " {} holders |", size).unwrap();
+        }
+        md.push_str("\n| --- |");
+        md.push_str(&" ---: |".repeat(sizes.len()));
+        md.push('\n');
+        for results in &self.results {
+            for workload in SCALING_WORKLOADS {
+                write!(md, "| {} {} |", results.backend.name, workload).unwrap();
+                for &size in &sizes {
+                    match results.get(size, workload) {
+                        Some(result) => write!(md, " {} |", format_ns(result.summary.mean)),
+                        None => write!(md, " - |"),
+                    }
+                    .unwrap();
+                }
+                md.push('\n');
+            }
+        }
+        md
+    }
+}
@@ -0,0 +1,181 @@
+//! Resetting chain state between iterations.
+//!
+//! Workloads that send transactions change the state they run against, so a
+//! token minted to on every iteration has grown by the last sample. With a
+//! [`Reset`] set, [`crate::bench_middleware`] saves the state after a
+//! workload's setup and goes back to it before every iteration or every
+//! batch Criterion times, outside of the measurement.
+//!
+//! Anvil, and any node supporting `evm_snapshot` and `evm_revert`, saves
+//! its whole state. Arbiter has no snapshots of its own, so there the
+//! storage slots the workload declares with [`crate::Workload::written_slots`]
+//! are read after setup and written back with cheatcodes instead. A reset
+//! there fails for a workload that declares none, rather than leaving its
+//! state to grow unnoticed.
+//!
+//! Only storage is restored on Arbiter. Account nonces and balances aren't,
+//! so a workload whose iterations deploy contracts sees new addresses every
+//! time, and one that moves ether keeps moving it from a shrinking balance.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use arbiter_core::{
+    environment::cheatcodes::{Cheatcodes, CheatcodesReturn},
+    middleware::RevmMiddleware,
+};
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{JsonRpcClient, Middleware, Provider},
+    signers::LocalWallet,
+    types::{Address, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+/// When to go back to the state saved after setup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reset {
+    /// Before every iteration, timing each one on its own.
+    Iteration,
+    /// Before each batch of iterations Criterion times together.
+    Batch,
+}
+
+impl FromStr for Reset {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        match raw {
+            "iteration" => Ok(Reset::Iteration),
+            "batch" => Ok(Reset::Batch),
+            _ => bail!("invalid reset {:?}, expected `iteration` or `batch`", raw),
+        }
+    }
+}
+
+/// Saves a backend's state and goes back to it.
+#[async_trait]
+pub trait Snapshots: Send + Sync {
+    /// Saves the current state, replacing anything saved before. `slots`
+    /// are the storage slots about to be written, for backends that can
+    /// only restore those.
+    async fn snapshot(&self, slots: &[(Address, H256)]) -> Result<()>;
+
+    /// Goes back to the saved state, which stays saved.
+    async fn revert(&self) -> Result<()>;
+
+    /// Whether only the slots passed to [`Snapshots::snapshot`] are restored,
+    /// so that nothing is when none are.
+    fn declared_slots_only(&self) -> bool {
+        false
+    }
+}
+
+/// A backend's middleware that its state can be saved through.
+pub trait Revertible: Middleware + 'static {
+    fn snapshots(client: Arc<Self>) -> Arc<dyn Snapshots>;
+}
+
+impl Revertible for RevmMiddleware {
+    fn snapshots(client: Arc<Self>) -> Arc<dyn Snapshots> {
+        Arc::new(ArbiterSnapshots {
+            client,
+            saved: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+impl<T: JsonRpcClient + 'static> Revertible for SignerMiddleware<Provider<T>, LocalWallet> {
+    fn snapshots(client: Arc<Self>) -> Arc<dyn Snapshots> {
+        Arc::new(NodeSnapshots::new(client))
+    }
+}
+
+/// Snapshots taken with `evm_snapshot` and restored with `evm_revert`.
+pub struct NodeSnapshots<M> {
+    client: Arc<M>,
+    id: Mutex<Option<U256>>,
+}
+
+impl<M: Middleware> NodeSnapshots<M> {
+    pub fn new(client: Arc<M>) -> Self {
+        Self {
+            client,
+            id: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Snapshots for NodeSnapshots<M> {
+    async fn snapshot(&self, _slots: &[(Address, H256)]) -> Result<()> {
+        let id: U256 = self.client.provider().request("evm_snapshot", ()).await?;
+        *self.id.lock().unwrap() = Some(id);
+        Ok(())
+    }
+
+    async fn revert(&self) -> Result<()> {
+        let id = self
+            .id
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow!("no state was saved to revert to"))?;
+        let reverted: bool = self.client.provider().request("evm_revert", [id]).await?;
+        if !reverted {
+            bail!("the node has no snapshot {}", id);
+        }
+        // reverting discards the snapshot, so the state is saved again
+        self.snapshot(&[]).await
+    }
+}
+
+/// Storage slots read and written back through Arbiter's cheatcodes.
+pub struct ArbiterSnapshots {
+    client: Arc<RevmMiddleware>,
+    saved: Mutex<Vec<(Address, H256, H256)>>,
+}
+
+#[async_trait]
+impl Snapshots for ArbiterSnapshots {
+    async fn snapshot(&self, slots: &[(Address, H256)]) -> Result<()> {
+        let mut saved = Vec::with_capacity(slots.len());
+        for &(account, key) in slots {
+            let load = Cheatcodes::Load {
+                account,
+                key,
+                block: None,
+            };
+            match self.client.apply_cheatcode(load).await? {
+                CheatcodesReturn::Load { value } => {
+                    saved.push((account, key, H256::from(value.to_be_bytes::<32>())))
+                }
+                other => bail!("unexpected cheatcode return {:?}", other),
+            }
+        }
+        *self.saved.lock().unwrap() = saved;
+        Ok(())
+    }
+
+    fn declared_slots_only(&self) -> bool {
+        true
+    }
+
+    async fn revert(&self) -> Result<()> {
+        let saved = self.saved.lock().unwrap().clone();
+        for (account, key, value) in saved {
+            let store = Cheatcodes::Store {
+                account,
+                key,
+                value,
+            };
+            self.client.apply_cheatcode(store).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! What each standard ethers middleware layer costs.
+//!
+//! Production clients rarely talk to a bare provider: they stack a
+//! `GasOracleMiddleware`, a `SignerMiddleware` and a `NonceManagerMiddleware`
+//! on top of it, innermost first, as the ethers docs recommend. Here a
+//! backend's middleware is taken apart into its base, and the same workloads
+//! are benchmarked with Criterion while adding those layers back one at a
+//! time. The difference in mean time between one level and the one below it
+//! is the marginal cost of the layer added.
+//!
+//! Without a signer, transactions are sent unsigned for the node to sign
+//! with an account it holds, as Anvil and the mock node do for their dev
+//! account. Arbiter never signs, so it gets no signer layer.
+
+use anyhow::{anyhow, Ok, Result};
+use arbiter_core::middleware::RevmMiddleware;
+use criterion::Criterion;
+use ethers::{
+    middleware::{
+        gas_oracle::{GasOracleMiddleware, ProviderOracle},
+        NonceManagerMiddleware, SignerMiddleware,
+    },
+    providers::{JsonRpcClient, Middleware, Provider},
+    signers::LocalWallet,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, path::Path, sync::Arc};
+
+use crate::{
+    bench_middleware,
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, BenchResults},
+    workload::WorkloadFactory,
+};
+
+/// The levels benchmarked, each adding one layer to the one before.
+pub const LAYERS: &[&str] = &["Base", "GasOracle", "Signer", "NonceManager"];
+
+/// A backend's middleware, taken apart into what the layers are stacked on.
+pub trait Unstack: Middleware + 'static {
+    type Base: Middleware + 'static;
+
+    /// The middleware without any layers, with the sender set, along with
+    /// the wallet to sign with if transactions are signed on the client.
+    fn unstack(client: Arc<Self>) -> (Arc<Self::Base>, Option<LocalWallet>);
+}
+
+impl Unstack for RevmMiddleware {
+    type Base = RevmMiddleware;
+
+    fn unstack(client: Arc<Self>) -> (Arc<Self>, Option<LocalWallet>) {
+        (client, None)
+    }
+}
+
+impl<T: JsonRpcClient + Clone + 'static> Unstack for SignerMiddleware<Provider<T>, LocalWallet> {
+    type Base = Provider<T>;
+
+    fn unstack(client: Arc<Self>) -> (Arc<Provider<T>>, Option<LocalWallet>) {
+        let provider = client.inner().clone().with_sender(client.address());
+        (Arc::new(provider), Some(client.signer().clone()))
+    }
+}
+
+/// The workloads benchmarked at one level of the stack.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    /// The layer added at this level, one of [`LAYERS`].
+    pub name: String,
+    pub results: BenchResults,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackResults {
+    pub backend: BackendInfo,
+    /// In the order they were stacked, starting from the base.
+    pub layers: Vec<Layer>,
+}
+
+impl StackResults {
+    /// How much `layer` adds to the mean time of `workload` in nanoseconds,
+    /// or `None` for the base or a layer that wasn't benchmarked.
+    pub fn marginal_ns(&self, layer: &str, workload: &str) -> Option<f64> {
+        let index = self.layers.iter().position(|l| l.name == layer)?;
+        let below = self.layers.get(index.checked_sub(1)?)?;
+        let mean = |layer: &Layer| Some(layer.results.get(workload)?.summary.mean);
+        Some(mean(&self.layers[index])? - mean(below)?)
+    }
+}
+
+/// Benchmarks the workloads from `workloads` on `client`'s base, then again
+/// after each of the layers in [`LAYERS`] is added on top.
+pub async fn stack_middleware<M: Unstack>(
+    c: &mut Criterion,
+    client: Arc<M>,
+    label: &str,
+    workloads: &impl WorkloadFactory,
+) -> Result<StackResults> {
+    let backend = BackendInfo::query(label, &*client).await;
+    let (base, wallet) = M::unstack(client);
+    let sender = base
+        .default_sender()
+        .ok_or_else(|| anyhow!("client has no default sender"))?;
+    let mut layers = vec![layer(c, base.clone(), label, "Base", workloads).await?];
+
+    let oracle = Arc::new(GasOracleMiddleware::new(
+        base.clone(),
+        ProviderOracle::new(base),
+    ));
+    layers.push(layer(c, oracle.clone(), label, "GasOracle", workloads).await?);
+    match wallet {
+        Some(wallet) => {
+            let signer = Arc::new(SignerMiddleware::new(oracle, wallet));
+            layers.push(layer(c, signer.clone(), label, "Signer", workloads).await?);
+            let nonces = Arc::new(NonceManagerMiddleware::new(signer, sender));
+            layers.push(layer(c, nonces, label, "NonceManager", workloads).await?);
+        }
+        None => {
+            let nonces = Arc::new(NonceManagerMiddleware::new(oracle, sender));
+            layers.push(layer(c, nonces, label, "NonceManager", workloads).await?);
+        }
+    }
+    Ok(StackResults { backend, layers })
+}
+
+async fn layer<M: Middleware + 'static>(
+    c: &mut Criterion,
+    client: Arc<M>,
+    label: &str,
+    name: &str,
+    workloads: &impl WorkloadFactory,
+) -> Result<Layer> {
+    let label = match name {
+        "Base" => label.to_string(),
+        _ => format!("{} +{}", label, name),
+    };
+    Ok(Layer {
+        name: name.to_string(),
+        results: bench_middleware(c, client, &label, workloads.build()?).await?,
+    })
+}
+
+/// The layer costs of a run, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackReport {
+    pub schema_version: u32,
+    pub results: Vec<StackResults>,
+}
+
+impl StackReport {
+    pub fn new(results: Vec<StackResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A row per workload and layer, with the mean time at that level and
+    /// what the layer added to it.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from(
+            "| Benchmark | Layer | Mean | Marginal |\n\
+             | --- | --- | ---: | ---: |\n",
+        );
+        for results in &self.results {
+            let Some(base) = results.layers.first() else {
+                continue;
+            };
+            for workload in &base.results.workloads {
+                for layer in &results.layers {
+                    let Some(result) = layer.results.get(&workload.workload) else {
+                        continue;
+                    };
+                    let marginal = match results.marginal_ns(&layer.name, &workload.workload) {
+                        Some(ns) if ns < 0.0 => format!("-{}", format_ns(-ns)),
+                        Some(ns) => format!("+{}", format_ns(ns)),
+                        None => "-".to_string(),
+                    };
+                    writeln!(
+                        md,
+                        "| {} {} | {} | {} | {} |",
+                        results.backend.name,
+                        workload.workload,
+                        layer.name,
+                        format_ns(result.summary.mean),
+                        marginal
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        md
+    }
+}
@@ -0,0 +1,832 @@
+//! Declarative benchmark suites.
+//!
+//! A suite is a TOML file listing the backends to benchmark, the contracts to
+//! deploy from Foundry artifacts and the calls to benchmark against them:
+//!
+//! ```toml
+//! backends = ["arbiter", "anvil"]
+//! workloads = ["Lookups"]
+//! warm_up_time = 1.0
+//!
+//! [[contracts]]
+//! name = "counter"
+//! artifact = "Counter"
+//!
+//! [[benchmarks]]
+//! name = "Set Number"
+//! contract = "counter"
+//! function = "setNumber(uint256)"
+//! args = ["42"]
+//! sample_size = 20
+//! measurement_time = 2.5
+//! ```
+//!
+//! `workloads` selects built-in workloads by name. Criterion settings given at
+//! the top level apply to every benchmark that doesn't set its own. Contract
+//! and call arguments are parsed against the ABI, and `$sender` stands for the
+//! backend's default sender. `reset = "iteration"` or `"batch"`, at either
+//! level, puts the state back to how it was after setup before each iteration
+//! or batch Criterion times, see [`crate::snapshot`].
+//!
+//! `anvil`, `anvil-ws` and `anvil-ipc` reach Anvil over HTTP, WebSocket and IPC
+//! respectively. When more than one is listed they share a single node.
+//!
+//! `rpc` benchmarks an external node given by an `[rpc]` table, and `mock`
+//! a [`crate::mock_node::MockNode`] that executes nothing:
+//!
+//! ```toml
+//! backends = ["rpc", "mock"]
+//!
+//! [rpc]
+//! url = "http://localhost:8545"
+//! key_env = "BENCH_KEY"
+//! chain_id = 31337
+//! ```
+//!
+//! The second account the transfer workloads send from has to be funded
+//! beforehand on `rpc`, unless `fund_accounts = true` lets the key top it up.
+//!
+//! `state` names a fixture written by `benchlayer fixture` for Arbiter and
+//! Anvil to start from instead of an empty chain, see [`crate::fixture`]. The
+//! `rpc` and `mock` backends are left as they are.
+//!
+//! A `[network]` table puts a [`crate::network::Proxy`] in front of every
+//! backend reached over TCP, so a local node can stand in for a remote one.
+//! Arbiter and `anvil-ipc` are left as they are:
+//!
+//! ```toml
+//! [network]
+//! latency_ms = 40
+//! jitter_ms = 10
+//! bandwidth_kbps = 10_000
+//! drop_rate = 0.001
+//! ```
+
+use anyhow::{anyhow, bail, Context, Ok, Result};
+use async_trait::async_trait;
+use criterion::Criterion;
+use ethers::providers::Middleware;
+use serde::Deserialize;
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    artifacts::{ArtifactCall, Artifacts},
+    backend::{
+        AnvilHttpBackend, AnvilIpcBackend, AnvilNode, AnvilWsBackend, ArbiterBackend, Backend,
+        MockBackend, RpcBackend,
+    },
+    bench_middleware,
+    compute::{compute_middleware, ComputeResults, ComputeSettings},
+    depth::{depth_middleware, DepthResults, DepthSettings},
+    instrumented::{calls_middleware, CallResults, CallSettings, Instrument},
+    network::NetworkConditions,
+    open_loop::{open_loop_middleware, OpenLoopResults, OpenLoopSettings},
+    phases::{phases_middleware, PhaseResults, PhaseSettings},
+    results::{BackendInfo, BenchResults},
+    scaling::{scaling_middleware, ScalingResults, ScalingSettings, SetStorage},
+    snapshot::{Reset, Revertible},
+    stack::{stack_middleware, StackResults, Unstack},
+    throughput::{nonce_managed, throughput_middleware, ThroughputResults, ThroughputSettings},
+    verify::{observe, Observations, VerifySettings},
+    workload::{
+        BenchSettings, WorkloadFactory, WorkloadRegistry, BUILTIN_WORKLOADS, PRECOMPILE_WORKLOADS,
+        TRANSFER_WORKLOADS,
+    },
+};
+
+/// Backends a suite can ask for.
+pub const BACKENDS: &[&str] = &["arbiter", "anvil", "anvil-ws", "anvil-ipc", "rpc", "mock"];
+
+/// The name results from the backend called `key` in [`BACKENDS`] are
+/// reported under, e.g. "Anvil WS" for `anvil-ws`.
+pub fn backend_name(key: &str) -> Option<&'static str> {
+    let name = match key {
+        "arbiter" => "Arbiter",
+        "anvil" => "Anvil",
+        "anvil-ws" => "Anvil WS",
+        "anvil-ipc" => "Anvil IPC",
+        "rpc" => "RPC",
+        "mock" => "Mock",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    pub backends: Vec<String>,
+    /// Foundry output directory. Defaults to the `out` configured by the
+    /// `foundry.toml` next to the suite file.
+    pub artifacts: Option<PathBuf>,
+    /// Built-in workloads to run alongside the suite's own benchmarks.
+    #[serde(default)]
+    pub workloads: Vec<String>,
+    #[serde(default)]
+    pub contracts: Vec<ContractSpec>,
+    #[serde(default)]
+    pub benchmarks: Vec<BenchmarkSpec>,
+    pub sample_size: Option<usize>,
+    /// Seconds.
+    pub measurement_time: Option<f64>,
+    /// Seconds.
+    pub warm_up_time: Option<f64>,
+    /// Go back to the state after setup before every `iteration` or `batch`.
+    pub reset: Option<Reset>,
+    /// The node the `rpc` backend connects to.
+    pub rpc: Option<RpcSpec>,
+    /// Simulated between the client and every backend reached over TCP.
+    pub network: Option<NetworkConditions>,
+    /// A [`crate::fixture::ChainState`] for Arbiter and Anvil to start from.
+    pub state: Option<PathBuf>,
+    /// Directory relative paths are resolved against.
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractSpec {
+    pub name: String,
+    /// Contract name in the Foundry output, defaults to `name`.
+    pub artifact: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchmarkSpec {
+    pub name: String,
+    pub contract: String,
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub sample_size: Option<usize>,
+    /// Seconds.
+    pub measurement_time: Option<f64>,
+    /// Seconds.
+    pub warm_up_time: Option<f64>,
+    pub reset: Option<Reset>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcSpec {
+    pub url: String,
+    /// Hex private key to sign transactions with.
+    pub key: Option<String>,
+    /// Environment variable holding the key, to keep it out of the file.
+    pub key_env: Option<String>,
+    /// Asked of the node when not given.
+    pub chain_id: Option<u64>,
+    /// Seconds between polls for a transaction's receipt.
+    pub poll_interval: Option<f64>,
+    /// Whether accounts workloads send from besides the key's, derived from
+    /// it, may be topped up with the key's ether.
+    #[serde(default)]
+    pub fund_accounts: bool,
+}
+
+impl RpcSpec {
+    pub fn validate(&self) -> Result<()> {
+        if self.key.is_some() == self.key_env.is_some() {
+            bail!("[rpc] needs exactly one of `key` and `key_env`");
+        }
+        if let Some(interval) = self.poll_interval {
+            if !(interval >= 0.0 && interval.is_finite()) {
+                bail!("[rpc] poll_interval must be a non-negative number of seconds");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn backend(&self) -> Result<RpcBackend> {
+        let key = match (&self.key, &self.key_env) {
+            (Some(key), _) => key.clone(),
+            (None, Some(var)) => {
+                std::env::var(var).with_context(|| format!("[rpc] key_env {} isn't set", var))?
+            }
+            (None, None) => bail!("[rpc] has no key"),
+        };
+        let mut backend = RpcBackend::new(&self.url, key);
+        if let Some(chain_id) = self.chain_id {
+            backend = backend.chain_id(chain_id);
+        }
+        if let Some(interval) = self.poll_interval {
+            backend = backend.poll_interval(Duration::from_secs_f64(interval));
+        }
+        Ok(backend.fund_accounts(self.fund_accounts))
+    }
+}
+
+impl BenchmarkSpec {
+    pub fn settings(&self) -> BenchSettings {
+        BenchSettings {
+            sample_size: self.sample_size,
+            measurement_time: self.measurement_time,
+            warm_up_time: self.warm_up_time,
+            reset: self.reset,
+        }
+    }
+}
+
+impl Suite {
+    /// A suite running built-in workloads only.
+    pub fn builtin(backends: Vec<String>, workloads: Vec<String>) -> Self {
+        Self {
+            backends,
+            artifacts: None,
+            workloads,
+            contracts: Vec::new(),
+            benchmarks: Vec::new(),
+            sample_size: None,
+            measurement_time: None,
+            warm_up_time: None,
+            reset: None,
+            rpc: None,
+            network: None,
+            state: None,
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads and validates a suite file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read suite {}", path.display()))?;
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::parse(&raw, root).with_context(|| format!("invalid suite {}", path.display()))
+    }
+
+    /// Parses and validates a suite from TOML, with the paths in it relative
+    /// to `root`.
+    pub fn parse(raw: &str, root: impl Into<PathBuf>) -> Result<Self> {
+        let mut suite: Self = toml::from_str(raw)?;
+        suite.root = root.into();
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.validate_backends()?;
+        if self.workloads.is_empty() && self.benchmarks.is_empty() {
+            bail!("nothing to benchmark, add `workloads` or `[[benchmarks]]`");
+        }
+        let known: Vec<&str> = BUILTIN_WORKLOADS
+            .iter()
+            .chain(PRECOMPILE_WORKLOADS)
+            .chain(TRANSFER_WORKLOADS)
+            .copied()
+            .collect();
+        for workload in &self.workloads {
+            if !known.contains(&workload.as_str()) {
+                bail!(
+                    "unknown workload {:?}, expected one of {:?}",
+                    workload,
+                    known
+                );
+            }
+        }
+
+        unique("contract", self.contracts.iter().map(|c| &c.name))?;
+        unique(
+            "benchmark",
+            self.workloads
+                .iter()
+                .chain(self.benchmarks.iter().map(|b| &b.name)),
+        )?;
+        for benchmark in &self.benchmarks {
+            if !self.contracts.iter().any(|c| c.name == benchmark.contract) {
+                bail!(
+                    "benchmark {:?} uses undeclared contract {:?}",
+                    benchmark.name,
+                    benchmark.contract
+                );
+            }
+            benchmark
+                .settings()
+                .validate()
+                .with_context(|| format!("invalid settings for {:?}", benchmark.name))?;
+        }
+        self.settings().validate()
+    }
+
+    /// Checks the backends and what they're reached through, which is all
+    /// that modes ignoring the suite's workloads need.
+    pub fn validate_backends(&self) -> Result<()> {
+        if self.backends.is_empty() {
+            bail!("no backends given, expected some of {:?}", BACKENDS);
+        }
+        for backend in &self.backends {
+            if !BACKENDS.contains(&backend.as_str()) {
+                bail!(
+                    "unknown backend {:?}, expected one of {:?}",
+                    backend,
+                    BACKENDS
+                );
+            }
+        }
+        unique("backend", self.backends.iter())?;
+        match &self.rpc {
+            Some(rpc) => rpc.validate()?,
+            None if self.backends.iter().any(|backend| backend == "rpc") => {
+                bail!("the rpc backend needs an [rpc] table")
+            }
+            None => {}
+        }
+        if let Some(network) = &self.network {
+            network.validate()?;
+        }
+        if let Some(path) = self.state_path() {
+            if !path.is_file() {
+                bail!("state {} doesn't exist", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// The name results from the backend called `key` are reported under,
+    /// which has to be one of the suite's.
+    pub fn backend_name(&self, key: &str) -> Result<&'static str> {
+        self.backends
+            .iter()
+            .find(|backend| backend.eq_ignore_ascii_case(key))
+            .and_then(|backend| backend_name(backend))
+            .ok_or_else(|| anyhow!("{} isn't one of the backends run", key))
+    }
+
+    fn state_path(&self) -> Option<PathBuf> {
+        Some(self.root.join(self.state.as_ref()?))
+    }
+
+    /// The default Criterion settings for the suite's benchmarks.
+    pub fn settings(&self) -> BenchSettings {
+        BenchSettings {
+            sample_size: self.sample_size,
+            measurement_time: self.measurement_time,
+            warm_up_time: self.warm_up_time,
+            reset: self.reset,
+        }
+    }
+
+    /// Whether any of the workloads sends from a second account.
+    fn needs_spender(&self) -> bool {
+        self.workloads
+            .iter()
+            .any(|name| TRANSFER_WORKLOADS.contains(&name.as_str()))
+    }
+
+    pub fn artifacts(&self) -> Result<Artifacts> {
+        match &self.artifacts {
+            Some(out) => Ok(Artifacts::new(self.root.join(out))),
+            None => Artifacts::from_foundry_project(&self.root),
+        }
+    }
+
+    /// Builds the workloads for a single backend. The transfer workloads
+    /// need `spender`, a second account on it.
+    pub fn workloads<M: Middleware + 'static>(
+        &self,
+        artifacts: &Artifacts,
+        spender: Option<Arc<M>>,
+    ) -> Result<WorkloadRegistry<M>> {
+        let names: Vec<&str> = self.workloads.iter().map(String::as_str).collect();
+        let mut registry = WorkloadRegistry::available();
+        if let Some(spender) = spender {
+            registry.append(WorkloadRegistry::transfers(spender));
+        } else if self.needs_spender() {
+            bail!("the transfer workloads need a second account, which this run doesn't set up");
+        }
+        let mut registry = registry.select(&names)?;
+        registry.defaults(self.settings());
+        for benchmark in &self.benchmarks {
+            // validated to exist
+            let contract = self
+                .contracts
+                .iter()
+                .find(|c| c.name == benchmark.contract)
+                .unwrap();
+            let artifact = artifacts.get(contract.artifact.as_ref().unwrap_or(&contract.name))?;
+            // surface bad signatures before any backend is started
+            let function = artifact.function(&benchmark.function)?;
+            if function.inputs.len() != benchmark.args.len() {
+                bail!(
+                    "benchmark {:?} passes {} arguments to {}, which takes {}",
+                    benchmark.name,
+                    benchmark.args.len(),
+                    benchmark.function,
+                    function.inputs.len()
+                );
+            }
+            registry.register_with(
+                ArtifactCall::new(
+                    &benchmark.name,
+                    artifact,
+                    &benchmark.function,
+                    benchmark.args.clone(),
+                )
+                .constructor_args(contract.args.clone()),
+                benchmark.settings(),
+            );
+        }
+        Ok(registry)
+    }
+
+    /// Benchmarks the suite against each of its backends in turn.
+    pub async fn run(&self, c: &mut Criterion) -> Result<Vec<BenchResults>> {
+        self.each_backend(Bench(c)).await
+    }
+
+    /// Runs the suite's workloads in throughput mode against each backend.
+    /// Per-benchmark Criterion settings don't apply here.
+    pub async fn throughput(
+        &self,
+        settings: &ThroughputSettings,
+    ) -> Result<Vec<ThroughputResults>> {
+        self.each_backend(Throughput(settings)).await
+    }
+
+    /// Runs the suite's workloads at a constant rate against each backend.
+    pub async fn open_loop(&self, settings: &OpenLoopSettings) -> Result<Vec<OpenLoopResults>> {
+        self.each_backend(OpenLoop(settings)).await
+    }
+
+    /// Breaks a minting transaction down into its phases on each backend.
+    /// The suite's workloads don't apply here.
+    pub async fn phases(&self, settings: &PhaseSettings) -> Result<Vec<PhaseResults>> {
+        self.each_backend(Phases(settings)).await
+    }
+
+    /// Records the middleware calls the suite's workloads make on each
+    /// backend.
+    pub async fn calls(&self, settings: &CallSettings) -> Result<Vec<CallResults>> {
+        self.each_backend(Calls(settings)).await
+    }
+
+    /// Benchmarks the suite's workloads on each backend while stacking the
+    /// standard ethers middleware layers on it one at a time.
+    pub async fn stack(&self, c: &mut Criterion) -> Result<Vec<StackResults>> {
+        self.each_backend(Stack(c)).await
+    }
+
+    /// Benchmarks lookups and mints on each backend as the state of the
+    /// token they use grows. Only the suite's Criterion settings apply.
+    pub async fn scaling(
+        &self,
+        c: &mut Criterion,
+        settings: &ScalingSettings,
+    ) -> Result<Vec<ScalingResults>> {
+        self.each_backend(Scaling(c, settings)).await
+    }
+
+    /// Benchmarks the compute contract's loops on each backend at each of
+    /// the loop counts. Only the suite's Criterion settings apply.
+    pub async fn compute(
+        &self,
+        c: &mut Criterion,
+        settings: &ComputeSettings,
+    ) -> Result<Vec<ComputeResults>> {
+        self.each_backend(Compute(c, settings)).await
+    }
+
+    /// Benchmarks calls nested through a chain of relays on each backend at
+    /// each of the depths. Only the suite's Criterion settings apply.
+    pub async fn depth(
+        &self,
+        c: &mut Criterion,
+        settings: &DepthSettings,
+    ) -> Result<Vec<DepthResults>> {
+        self.each_backend(Depth(c, settings)).await
+    }
+
+    /// Runs the verification sequence on each backend, recording what it
+    /// sees to diff against the others. The suite's workloads don't apply
+    /// here.
+    pub async fn verify(&self, settings: &VerifySettings) -> Result<Vec<Observations>> {
+        self.each_backend(Verify(settings)).await
+    }
+
+    async fn each_backend<R: Mode>(&self, mut mode: R) -> Result<Vec<R::Results>> {
+        let artifacts = self.artifacts()?;
+        // the Anvil transports share a node, so they're compared on one chain
+        let mut anvil: Option<Arc<AnvilNode>> = None;
+        let state = self.state_path();
+        let mut node = || {
+            anvil
+                .get_or_insert_with(|| AnvilNode::spawn_from(state.as_deref()))
+                .clone()
+        };
+        let mut results = Vec::with_capacity(self.backends.len());
+        for backend in &self.backends {
+            let result = match backend.as_str() {
+                "arbiter" => {
+                    let arbiter = ArbiterBackend::default().load_state(self.state_path());
+                    self.run_on(arbiter, &artifacts, &mut mode).await?
+                }
+                "anvil" => {
+                    let anvil = AnvilHttpBackend::on(node()).through(self.network);
+                    self.run_on(anvil, &artifacts, &mut mode).await?
+                }
+                "anvil-ws" => {
+                    let anvil = AnvilWsBackend::on(node()).through(self.network);
+                    self.run_on(anvil, &artifacts, &mut mode).await?
+                }
+                "anvil-ipc" => {
+                    self.run_on(AnvilIpcBackend::on(node()), &artifacts, &mut mode)
+                        .await?
+                }
+                "rpc" => {
+                    // validated to exist
+                    let rpc = self.rpc.as_ref().unwrap().backend()?.through(self.network);
+                    self.run_on(rpc, &artifacts, &mut mode).await?
+                }
+                "mock" => {
+                    let mock = MockBackend::default().through(self.network);
+                    self.run_on(mock, &artifacts, &mut mode).await?
+                }
+                other => bail!("unknown backend {:?}", other),
+            };
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    async fn run_on<B: Backend, R: Mode>(
+        &self,
+        mut backend: B,
+        artifacts: &Artifacts,
+        mode: &mut R,
+    ) -> Result<R::Results>
+    where
+        B::Middleware: SuiteMiddleware,
+    {
+        let client = backend.start().await?;
+        let results = async {
+            let spender = match R::RUNS_WORKLOADS && self.needs_spender() {
+                true => Some(backend.account(&client, "spender").await?),
+                false => None,
+            };
+            let started = Started {
+                info: backend.info(&client).await,
+                name: backend.name().to_string(),
+                client,
+                spender,
+                artifacts,
+            };
+            mode.run(self, started).await
+        }
+        .await;
+        // torn down either way so a failed run doesn't leave a node behind,
+        // reporting the run's error over the teardown's
+        let torn_down = backend.teardown().await;
+        let results = results?;
+        torn_down?;
+        Ok(results)
+    }
+}
+
+impl WorkloadFactory for Suite {
+    fn build<M: Middleware + 'static>(&self) -> Result<WorkloadRegistry<M>> {
+        self.workloads(&self.artifacts()?, None)
+    }
+}
+
+/// What every mode needs of a backend's middleware.
+trait SuiteMiddleware: Unstack + Revertible + SetStorage + Instrument {}
+
+impl<M: Unstack + Revertible + SetStorage + Instrument> SuiteMiddleware for M {}
+
+/// A backend started by [`Suite::run_on`].
+struct Started<'a, M> {
+    client: Arc<M>,
+    /// A second account on the backend, when a workload needs one.
+    spender: Option<Arc<M>>,
+    /// The label results are reported under.
+    name: String,
+    info: BackendInfo,
+    artifacts: &'a Artifacts,
+}
+
+/// How [`Suite::each_backend`] runs the workloads, giving results of its own
+/// type for each backend.
+#[async_trait(?Send)]
+trait Mode {
+    type Results;
+    /// Whether it runs the registry from [`Suite::workloads`], and so needs a
+    /// second account when the transfer workloads are listed.
+    const RUNS_WORKLOADS: bool = false;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<Self::Results>;
+}
+
+struct Bench<'a>(&'a mut Criterion);
+
+#[async_trait(?Send)]
+impl Mode for Bench<'_> {
+    type Results = BenchResults;
+    const RUNS_WORKLOADS: bool = true;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<BenchResults> {
+        let mut workloads = suite.workloads(started.artifacts, started.spender)?;
+        workloads.snapshots(M::snapshots(started.client.clone()));
+        let mut results =
+            bench_middleware(self.0, started.client, &started.name, workloads).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Throughput<'a>(&'a ThroughputSettings);
+
+#[async_trait(?Send)]
+impl Mode for Throughput<'_> {
+    type Results = ThroughputResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<ThroughputResults> {
+        let mut results =
+            throughput_middleware(started.client, &started.name, suite, self.0).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct OpenLoop<'a>(&'a OpenLoopSettings);
+
+#[async_trait(?Send)]
+impl Mode for OpenLoop<'_> {
+    type Results = OpenLoopResults;
+    const RUNS_WORKLOADS: bool = true;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<OpenLoopResults> {
+        // the spender's calls overlap too
+        let spender = started.spender.map(nonce_managed).transpose()?;
+        let workloads = suite.workloads(started.artifacts, spender)?;
+        let mut results =
+            open_loop_middleware(started.client, &started.name, workloads, self.0).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Phases<'a>(&'a PhaseSettings);
+
+#[async_trait(?Send)]
+impl Mode for Phases<'_> {
+    type Results = PhaseResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        _suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<PhaseResults> {
+        // only a client with a wallet of its own signs
+        let (_, wallet) = M::unstack(started.client.clone());
+        let signs = wallet.is_some();
+        let mut results = phases_middleware(started.client, &started.name, signs, self.0).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Calls<'a>(&'a CallSettings);
+
+#[async_trait(?Send)]
+impl Mode for Calls<'_> {
+    type Results = CallResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<CallResults> {
+        let mut results = calls_middleware(started.client, &started.name, suite, self.0).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Stack<'a>(&'a mut Criterion);
+
+#[async_trait(?Send)]
+impl Mode for Stack<'_> {
+    type Results = StackResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<StackResults> {
+        let mut results = stack_middleware(self.0, started.client, &started.name, suite).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Scaling<'a>(&'a mut Criterion, &'a ScalingSettings);
+
+#[async_trait(?Send)]
+impl Mode for Scaling<'_> {
+    type Results = ScalingResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<ScalingResults> {
+        let bench = suite.settings();
+        let mut results =
+            scaling_middleware(self.0, started.client, &started.name, self.1, &bench).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Compute<'a>(&'a mut Criterion, &'a ComputeSettings);
+
+#[async_trait(?Send)]
+impl Mode for Compute<'_> {
+    type Results = ComputeResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<ComputeResults> {
+        let bench = suite.settings();
+        let mut results =
+            compute_middleware(self.0, started.client, &started.name, self.1, &bench).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Depth<'a>(&'a mut Criterion, &'a DepthSettings);
+
+#[async_trait(?Send)]
+impl Mode for Depth<'_> {
+    type Results = DepthResults;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<DepthResults> {
+        let bench = suite.settings();
+        let mut results =
+            depth_middleware(self.0, started.client, &started.name, self.1, &bench).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+struct Verify<'a>(&'a VerifySettings);
+
+#[async_trait(?Send)]
+impl Mode for Verify<'_> {
+    type Results = Observations;
+
+    async fn run<M: SuiteMiddleware>(
+        &mut self,
+        _suite: &Suite,
+        started: Started<'_, M>,
+    ) -> Result<Observations> {
+        let mut results = observe(started.client, &started.name, self.0).await?;
+        results.backend = started.info;
+        Ok(results)
+    }
+}
+
+fn unique<'a>(kind: &str, names: impl Iterator<Item = &'a String>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            bail!("duplicate {} {:?}", kind, name);
+        }
+    }
+    Ok(())
+}
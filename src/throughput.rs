@@ -0,0 +1,262 @@
+//! Closed-loop throughput benchmarking.
+//!
+//! Criterion only ever has one request in flight. Here a workload is run by
+//! `N` concurrent workers against the same middleware for a fixed duration,
+//! each starting its next call as soon as the previous one returns, and the
+//! sustained rate and per-call latency are reported for each `N`.
+//!
+//! Concurrent transactions from one sender would each look up the same
+//! pending nonce, and all but one would be rejected, so the workers share a
+//! `NonceManagerMiddleware` that hands nonces out locally. A level where more
+//! than [`MAX_ERROR_RATE`] of the calls fail fails the run, rather than
+//! reporting how fast requests are rejected.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use ethers::{middleware::NonceManagerMiddleware, providers::Middleware};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    report::{format_ns, save_json_or_markdown, SCHEMA_VERSION},
+    results::{BackendInfo, Summary},
+    workload::{Workload, WorkloadFactory},
+};
+
+/// The fraction of calls at a level that may fail before the run does.
+pub const MAX_ERROR_RATE: f64 = 0.01;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputSettings {
+    /// Numbers of calls to keep in flight, each measured separately.
+    pub concurrency: Vec<usize>,
+    /// Seconds to measure each concurrency level for.
+    pub duration: f64,
+    /// Seconds to run each concurrency level for before measuring.
+    pub warm_up: f64,
+}
+
+impl Default for ThroughputSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: vec![1, 4, 16, 64],
+            duration: 5.0,
+            warm_up: 1.0,
+        }
+    }
+}
+
+impl ThroughputSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.concurrency.is_empty() || self.concurrency.contains(&0) {
+            bail!("concurrency levels must be at least 1");
+        }
+        if !(self.duration > 0.0 && self.duration.is_finite()) {
+            bail!("duration must be a positive number of seconds");
+        }
+        if !(self.warm_up >= 0.0 && self.warm_up.is_finite()) {
+            bail!("warm-up must be a non-negative number of seconds");
+        }
+        Ok(())
+    }
+}
+
+/// One workload at one concurrency level.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub concurrency: usize,
+    /// Calls that completed successfully.
+    pub operations: u64,
+    /// Calls that returned an error, at most [`MAX_ERROR_RATE`] of them.
+    /// They aren't counted in the rate or the latencies.
+    pub errors: u64,
+    /// What one of the failed calls returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+    pub elapsed_ns: f64,
+    pub ops_per_sec: f64,
+    /// Per-call latency in nanoseconds.
+    pub latency: Summary,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkloadThroughput {
+    /// The backend and workload together, e.g. "Arbiter Stateful Call".
+    pub label: String,
+    pub workload: String,
+    pub levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputResults {
+    pub backend: BackendInfo,
+    pub workloads: Vec<WorkloadThroughput>,
+}
+
+/// A client handing out its sender's nonces locally, so calls in flight at
+/// once don't all take the same pending one.
+pub type NonceManaged<M> = NonceManagerMiddleware<Arc<M>>;
+
+/// Puts `client` behind a nonce manager for its default sender.
+pub fn nonce_managed<M: Middleware>(client: Arc<M>) -> Result<Arc<NonceManaged<M>>> {
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| anyhow!("client has no default sender"))?;
+    Ok(Arc::new(NonceManagerMiddleware::new(client, sender)))
+}
+
+/// Runs every workload from `workloads` at each concurrency level in
+/// `settings` in turn, on `client` behind a nonce manager.
+pub async fn throughput_middleware<M: Middleware + 'static>(
+    client: Arc<M>,
+    label: &str,
+    workloads: &impl WorkloadFactory,
+    settings: &ThroughputSettings,
+) -> Result<ThroughputResults> {
+    settings.validate()?;
+    let mut results = ThroughputResults {
+        backend: BackendInfo::query(label, &*client).await,
+        workloads: Vec::new(),
+    };
+    let client = nonce_managed(client)?;
+    let mut workloads = workloads.build()?;
+    for (workload, _) in workloads.iter_mut() {
+        workload.setup(client.clone()).await?;
+        let name = workload.name().to_string();
+        let mut levels = Vec::with_capacity(settings.concurrency.len());
+        for &concurrency in &settings.concurrency {
+            println!("{} {}: {} in flight", label, name, concurrency);
+            drive(&**workload, concurrency, secs(settings.warm_up)).await;
+            let level = drive(&**workload, concurrency, secs(settings.duration)).await;
+            println!(
+                "    {:.1} ops/s, p50 {}, p99 {}, {} errors",
+                level.ops_per_sec,
+                format_ns(level.latency.p50),
+                format_ns(level.latency.p99),
+                level.errors
+            );
+            let calls = level.operations + level.errors;
+            if level.errors as f64 > MAX_ERROR_RATE * calls as f64 {
+                bail!(
+                    "{} of {} calls of {} {} failed with {} in flight: {}",
+                    level.errors,
+                    calls,
+                    label,
+                    name,
+                    concurrency,
+                    level.first_error.as_deref().unwrap_or_default()
+                );
+            }
+            levels.push(level);
+        }
+        workload.teardown().await?;
+        results.workloads.push(WorkloadThroughput {
+            label: format!("{} {}", label, name),
+            workload: name,
+            levels,
+        });
+    }
+    Ok(results)
+}
+
+fn secs(seconds: f64) -> Duration {
+    Duration::from_secs_f64(seconds)
+}
+
+/// Keeps `concurrency` calls in flight until `duration` has passed.
+async fn drive<M: Middleware + 'static>(
+    workload: &dyn Workload<M>,
+    concurrency: usize,
+    duration: Duration,
+) -> Level {
+    let start = Instant::now();
+    let deadline = start + duration;
+    let workers = (0..concurrency).map(|_| async move {
+        let mut latencies = Vec::new();
+        let mut errors = 0;
+        let mut first_error = None;
+        while Instant::now() < deadline {
+            let call = Instant::now();
+            match workload.run().await {
+                Result::Ok(()) => latencies.push(call.elapsed().as_nanos() as f64),
+                Err(err) => {
+                    errors += 1;
+                    first_error.get_or_insert_with(|| format!("{:#}", err));
+                }
+            }
+        }
+        (latencies, errors, first_error)
+    });
+    let finished = join_all(workers).await;
+    let elapsed = start.elapsed();
+
+    let mut latencies = Vec::new();
+    let mut errors = 0;
+    let mut first_error = None;
+    for (worker_latencies, worker_errors, worker_error) in finished {
+        latencies.extend(worker_latencies);
+        errors += worker_errors;
+        first_error = first_error.or(worker_error);
+    }
+    Level {
+        concurrency,
+        operations: latencies.len() as u64,
+        errors,
+        first_error,
+        elapsed_ns: elapsed.as_nanos() as f64,
+        ops_per_sec: latencies.len() as f64 / elapsed.as_secs_f64(),
+        latency: Summary::from_times(&latencies),
+    }
+}
+
+/// The throughput results of a run, saved like a [`crate::report::Report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputReport {
+    pub schema_version: u32,
+    pub results: Vec<ThroughputResults>,
+}
+
+impl ThroughputReport {
+    pub fn new(results: Vec<ThroughputResults>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from(
+            "| Benchmark | In flight | Ops/s | p50 | p90 | p99 | Max | Errors |\n\
+             | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: |\n",
+        );
+        for workload in self.results.iter().flat_map(|results| &results.workloads) {
+            for level in &workload.levels {
+                writeln!(
+                    md,
+                    "| {} | {} | {:.1} | {} | {} | {} | {} | {} |",
+                    workload.label,
+                    level.concurrency,
+                    level.ops_per_sec,
+                    format_ns(level.latency.p50),
+                    format_ns(level.latency.p90),
+                    format_ns(level.latency.p99),
+                    format_ns(level.latency.max),
+                    level.errors
+                )
+                .unwrap();
+            }
+        }
+        md
+    }
+}
@@ -1,70 +1,105 @@
-// Todo allow for users to pick the contracts they want to benchmark with
 #![allow(dead_code)]
-use anyhow::{Ok, Result};
-use arbiter_core::{
-    bindings::{
-        arbiter_math::{self, ArbiterMath},
-        arbiter_token::{self, ArbiterToken},
-    },
-    environment::builder::EnvironmentBuilder,
-    middleware::RevmMiddleware,
+use anyhow::{ensure, Ok, Result};
+use arbiter_core::bindings::{
+    arbiter_math::{self, ArbiterMath},
+    arbiter_token::{self, ArbiterToken},
 };
-use ethers::{
-    core::{k256::ecdsa::SigningKey, utils::Anvil},
-    middleware::SignerMiddleware,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer, Wallet},
-};
-
-use std::{convert::TryFrom, sync::Arc, time::Duration};
-
-pub async fn get_middleware() -> Result<(
-    Arc<RevmMiddleware>,
-    Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-)> {
-    let arbiter = arbiter_startup()?;
-    let anvil = anvil_startup().await?;
-    Ok((arbiter, anvil))
-}
-
-fn arbiter_startup() -> Result<Arc<RevmMiddleware>> {
-    let environment = EnvironmentBuilder::new().build();
-    let client = RevmMiddleware::new(&environment, Some("name"))?;
-    Ok(client)
-}
-
-async fn anvil_startup() -> Result<Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>> {
-    // Create an Anvil instance
-    // No blocktime mines a new block for each tx, which is fastest.
-    let anvil = Anvil::new().spawn();
+use ethers::{providers::Middleware, types::Bytes};
+use tokio::{net::TcpListener, sync::oneshot};
 
-    // Create a client
-    let provider = Provider::<Http>::try_from(anvil.endpoint())
-        .unwrap()
-        .interval(Duration::ZERO);
-
-    let wallet: LocalWallet = anvil.keys()[0].clone().into();
-    let client = Arc::new(SignerMiddleware::new(
-        provider,
-        wallet.with_chain_id(anvil.chain_id()),
-    ));
-    Ok(client)
-}
+use std::{future::Future, net::SocketAddr, sync::Arc, thread::JoinHandle};
 
 pub(crate) async fn deploy_contracts_for_benchmarks<M: Middleware + 'static>(
     client: Arc<M>,
 ) -> Result<(ArbiterMath<M>, ArbiterToken<M>)> {
     println!("Deploying contracts for benchmarks");
-    let math = arbiter_math::ArbiterMath::deploy(client.clone(), ())?
+    let math = deploy_math(client.clone()).await?;
+    println!("Deployed math contract");
+    let token = deploy_token(client).await?;
+    Ok((math, token))
+}
+
+pub(crate) async fn deploy_math<M: Middleware + 'static>(client: Arc<M>) -> Result<ArbiterMath<M>> {
+    let math = arbiter_math::ArbiterMath::deploy(client, ())?
         .send()
         .await?;
+    Ok(math)
+}
 
-    println!("Deployed math contract");
+pub(crate) async fn deploy_token<M: Middleware + 'static>(
+    client: Arc<M>,
+) -> Result<ArbiterToken<M>> {
     let token = arbiter_token::ArbiterToken::deploy(
-        client.clone(),
+        client,
         ("Token".to_string(), "TEST".to_string(), 18_u8),
     )?
     .send()
     .await?;
-    Ok((math, token))
+    Ok(token)
+}
+
+/// Fails unless the build script compiled `contract` from `contracts/`, as
+/// without solc it leaves the bytecode empty rather than breaking the build.
+pub(crate) fn ensure_compiled(contract: &str, bytecode: &Bytes) -> Result<()> {
+    ensure!(
+        !bytecode.is_empty(),
+        "{} wasn't compiled, install solc 0.8.19 or set SOLC to it and rebuild",
+        contract
+    );
+    Ok(())
+}
+
+/// A server on a local port, running on a thread and runtime of its own since
+/// a client blocking the caller's runtime, as Criterion's executor does, would
+/// starve it. Stopped when dropped.
+pub(crate) struct ServerThread {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerThread {
+    /// Binds a free local port and runs `serve` on it.
+    pub(crate) fn spawn<F, Fut>(serve: F) -> Result<Self>
+    where
+        F: FnOnce(TcpListener) -> Fut + Send + 'static,
+        Fut: Future<Output = ()>,
+    {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let (shutdown, stopped) = oneshot::channel();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let thread = std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let listener = TcpListener::from_std(listener).unwrap();
+                tokio::select! {
+                    _ = serve(listener) => {}
+                    _ = stopped => {}
+                }
+            })
+        });
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ServerThread {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
 }
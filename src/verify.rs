@@ -0,0 +1,324 @@
+//! Checking that backends agree before comparing their speed.
+//!
+//! [`observe`] runs a fixed sequence against a middleware, deploying a token
+//! and minting to the sender `mints` times, and records what it sees along
+//! the way: gas used and logs from every receipt, the return data of calls,
+//! token balances and raw storage slots. Backends are then diffed against
+//! the first one. Addresses that legitimately differ, the sender's and the
+//! contracts', are replaced with placeholders like `$sender` first.
+
+use anyhow::{anyhow, bail, Ok, Result};
+use arbiter_core::bindings::{arbiter_math::ArbiterMath, arbiter_token::ArbiterToken};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Log, TransactionReceipt, H256, I256, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Write, path::Path, sync::Arc};
+
+use crate::{
+    bench_functions::balance_of_slot,
+    report::{save_json_or_markdown, SCHEMA_VERSION},
+    results::BackendInfo,
+};
+
+/// Storage slots of the token read directly, covering its name, symbol,
+/// total supply and admin.
+const TOKEN_SLOTS: u64 = 7;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerifySettings {
+    /// Mints to send before reading the state back.
+    pub mints: usize,
+}
+
+impl Default for VerifySettings {
+    fn default() -> Self {
+        Self { mints: 10 }
+    }
+}
+
+impl VerifySettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.mints == 0 {
+            bail!("at least one mint is needed");
+        }
+        Ok(())
+    }
+}
+
+/// One thing seen while running the sequence, e.g. the gas used by a mint.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub key: String,
+    /// The value with addresses replaced, or the error the backend returned.
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Observations {
+    pub backend: BackendInfo,
+    /// In the order they were made.
+    pub observations: Vec<Observation>,
+}
+
+impl Observations {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.observations
+            .iter()
+            .find(|observation| observation.key == key)
+            .map(|observation| observation.value.as_str())
+    }
+}
+
+/// A key whose value differs between two backends, or that only one has.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Difference {
+    pub key: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// Everything `actual` saw differently from `expected`, in the order
+/// `expected` saw it.
+pub fn diff(expected: &Observations, actual: &Observations) -> Vec<Difference> {
+    let mut differences: Vec<Difference> = expected
+        .observations
+        .iter()
+        .filter_map(|observation| {
+            let value = actual.get(&observation.key);
+            (value != Some(observation.value.as_str())).then(|| Difference {
+                key: observation.key.clone(),
+                expected: Some(observation.value.clone()),
+                actual: value.map(str::to_string),
+            })
+        })
+        .collect();
+    differences.extend(
+        actual
+            .observations
+            .iter()
+            .filter(|observation| expected.get(&observation.key).is_none())
+            .map(|observation| Difference {
+                key: observation.key.clone(),
+                expected: None,
+                actual: Some(observation.value.clone()),
+            }),
+    );
+    differences
+}
+
+/// Runs the verification sequence on `client`, recording what it sees.
+/// Reads the backend doesn't support are recorded as errors rather than
+/// stopping the run, so that they show up in the diff.
+pub async fn observe<M: Middleware + 'static>(
+    client: Arc<M>,
+    label: &str,
+    settings: &VerifySettings,
+) -> Result<Observations> {
+    settings.validate()?;
+    let backend = BackendInfo::query(label, &*client).await;
+    let sender = client
+        .default_sender()
+        .ok_or_else(|| anyhow!("client has no default sender"))?;
+    println!("{}: verifying {} mints", label, settings.mints);
+
+    let (token, receipt) = ArbiterToken::deploy(
+        client.clone(),
+        ("Token".to_string(), "TEST".to_string(), 18_u8),
+    )?
+    .send_with_receipt()
+    .await?;
+    let (math, _) = ArbiterMath::deploy(client.clone(), ())?
+        .send_with_receipt()
+        .await?;
+    let mut recorder = Recorder {
+        names: vec![
+            (sender, "$sender"),
+            (token.address(), "$token"),
+            (math.address(), "$math"),
+        ],
+        observations: Vec::new(),
+    };
+    recorder.receipt("deploy token", &receipt);
+
+    let wad = U256::from(10_u128.pow(18));
+    for i in 1..=settings.mints {
+        let mint = token.mint(sender, wad * i);
+        let receipt = mint
+            .send()
+            .await?
+            .await?
+            .ok_or_else(|| anyhow!("mint {} was dropped", i))?;
+        recorder.receipt(&format!("mint {}", i), &receipt);
+    }
+
+    let balance = token.balance_of(sender).call().await;
+    recorder.record("balance_of($sender)", balance.map_err(Into::into));
+    let supply = token.total_supply().call().await;
+    recorder.record("total_supply()", supply.map_err(Into::into));
+    let iwad = I256::from(10_u128.pow(18));
+    let cdf = client.call(&math.cdf(iwad).tx, None).await;
+    recorder.record("cdf(1e18) return data", cdf.map_err(Into::into));
+
+    for slot in 0..TOKEN_SLOTS {
+        let value = client
+            .get_storage_at(token.address(), H256::from_low_u64_be(slot), None)
+            .await;
+        let key = format!("$token storage slot {}", slot);
+        recorder.record(&key, value.map(|v| format!("{:#x}", v)).map_err(Into::into));
+    }
+    let slot = balance_of_slot(sender);
+    let value = client.get_storage_at(token.address(), slot, None).await;
+    recorder.record(
+        "$token storage balanceOf[$sender]",
+        value.map(|v| format!("{:#x}", v)).map_err(Into::into),
+    );
+
+    Ok(Observations {
+        backend,
+        observations: recorder.observations,
+    })
+}
+
+struct Recorder {
+    /// Addresses to replace with placeholders.
+    names: Vec<(Address, &'static str)>,
+    observations: Vec<Observation>,
+}
+
+impl Recorder {
+    fn record(&mut self, key: &str, value: Result<impl ToString>) {
+        let value = match value {
+            Result::Ok(value) => self.rename(value.to_string()),
+            Err(err) => format!("error: {}", err),
+        };
+        self.observations.push(Observation {
+            key: key.to_string(),
+            value,
+        });
+    }
+
+    fn receipt(&mut self, key: &str, receipt: &TransactionReceipt) {
+        let status = receipt.status.map(|status| status.to_string());
+        self.record(&format!("{}: status", key), Ok(status.unwrap_or_default()));
+        let gas = receipt.gas_used.map(|gas| gas.to_string());
+        self.record(&format!("{}: gas used", key), Ok(gas.unwrap_or_default()));
+        self.record(
+            &format!("{}: logs", key),
+            Ok(receipt.logs.len().to_string()),
+        );
+        for (index, log) in receipt.logs.iter().enumerate() {
+            self.record(&format!("{}: log {}", key, index), Ok(describe(log)));
+        }
+    }
+
+    /// Replaces known addresses in `value`, whether `0x`-prefixed or padded
+    /// into a word.
+    fn rename(&self, mut value: String) -> String {
+        value = value.to_lowercase();
+        for (address, name) in &self.names {
+            value = value
+                .replace(&format!("{:#x}", address), name)
+                .replace(&format!("{:x}", address), name);
+        }
+        value
+    }
+}
+
+fn describe(log: &Log) -> String {
+    let topics: Vec<String> = log
+        .topics
+        .iter()
+        .map(|topic| format!("{:#x}", topic))
+        .collect();
+    format!(
+        "{:#x} topics [{}] data {}",
+        log.address,
+        topics.join(", "),
+        log.data
+    )
+}
+
+/// The observations of every backend, diffed against the first.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub schema_version: u32,
+    pub results: Vec<Observations>,
+}
+
+impl VerifyReport {
+    pub fn new(results: Vec<Observations>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            results,
+        }
+    }
+
+    /// Each backend after the first, along with how it differs from it.
+    pub fn differences(&self) -> Vec<(&str, Vec<Difference>)> {
+        let Some((expected, rest)) = self.results.split_first() else {
+            return Vec::new();
+        };
+        rest.iter()
+            .map(|actual| (actual.backend.name.as_str(), diff(expected, actual)))
+            .collect()
+    }
+
+    /// Whether every backend saw the same as the first.
+    pub fn passed(&self) -> bool {
+        self.differences()
+            .iter()
+            .all(|(_, differences)| differences.is_empty())
+    }
+
+    /// Writes the report as `.json` or `.md`, going by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_json_or_markdown(path.as_ref(), self, || self.to_markdown())
+    }
+
+    /// A diff of each backend against the first, `-` lines being what the
+    /// first saw and `+` lines the other.
+    pub fn to_markdown(&self) -> String {
+        let Some(expected) = self.results.first() else {
+            return String::new();
+        };
+        let total = expected.observations.len();
+        let mut md = String::new();
+        for (backend, differences) in self.differences() {
+            if differences.is_empty() {
+                writeln!(
+                    md,
+                    "{} matches {} on all {} values.",
+                    backend, expected.backend.name, total
+                )
+                .unwrap();
+                continue;
+            }
+            writeln!(
+                md,
+                "{} differs from {} in {} of {} values:\n\n```diff",
+                backend,
+                expected.backend.name,
+                differences.len(),
+                total
+            )
+            .unwrap();
+            for difference in differences {
+                let missing = "(missing)".to_string();
+                writeln!(
+                    md,
+                    "- {}: {}\n+ {}: {}",
+                    difference.key,
+                    difference.expected.as_ref().unwrap_or(&missing),
+                    difference.key,
+                    difference.actual.as_ref().unwrap_or(&missing)
+                )
+                .unwrap();
+            }
+            md.push_str("```\n");
+        }
+        md
+    }
+}
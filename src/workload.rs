@@ -0,0 +1,273 @@
+use anyhow::{anyhow, bail, Ok, Result};
+use async_trait::async_trait;
+use criterion::{measurement::WallTime, BenchmarkGroup};
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256},
+};
+
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    bench_functions::{Create, Lookups, StatefulCall, StatelessCall, TransferFrom},
+    precompiles::{Precompile, PrecompileCall},
+    snapshot::{Reset, Snapshots},
+};
+
+/// Names of the workloads in [`WorkloadRegistry::builtin`].
+pub const BUILTIN_WORKLOADS: &[&str] = &["Stateful Call", "Stateless Call", "Create", "Lookups"];
+
+/// Names of the workloads in [`WorkloadRegistry::transfers`].
+pub const TRANSFER_WORKLOADS: &[&str] = &["Transfer From"];
+
+/// Names of the workloads in [`WorkloadRegistry::precompiles`].
+pub const PRECOMPILE_WORKLOADS: &[&str] = &[
+    "Ecrecover",
+    "Sha256",
+    "Ripemd160",
+    "Identity",
+    "Modexp",
+    "Bn256 Add",
+    "Bn256 Mul",
+    "Bn256 Pairing",
+    "Blake2f",
+];
+
+/// A single thing to benchmark against a middleware.
+///
+/// `setup` and `teardown` run once, outside of the measurement, and `run` is
+/// the body that gets timed on every iteration. `gas` runs once after the
+/// measurement.
+#[async_trait]
+pub trait Workload<M: Middleware + 'static>: Send + Sync {
+    /// The name used in benchmark labels, e.g. "Stateful Call".
+    fn name(&self) -> &str;
+
+    /// Prepares any state the workload needs, such as deploying contracts.
+    async fn setup(&mut self, client: Arc<M>) -> Result<()>;
+
+    /// A single iteration of the workload.
+    async fn run(&self) -> Result<()>;
+
+    /// The gas one iteration uses: `gas_used` from the receipt of a
+    /// transaction, or `estimate_gas` of a call. `None` if the workload
+    /// doesn't measure it.
+    async fn gas(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// The storage slots `run` writes to, as contract and slot, which is all
+    /// that backends without snapshots of their own restore on a
+    /// [`Reset`].
+    fn written_slots(&self) -> Vec<(Address, H256)> {
+        Vec::new()
+    }
+
+    /// Cleans up after the workload has been benchmarked.
+    async fn teardown(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Criterion's default number of samples per benchmark.
+pub const DEFAULT_SAMPLE_SIZE: usize = 100;
+
+/// Criterion settings for a single workload. Anything left unset falls back to
+/// the registry's defaults, then to the configuration of the `Criterion` passed
+/// to [`crate::bench_middleware`]. The sample size is always set explicitly,
+/// defaulting to [`DEFAULT_SAMPLE_SIZE`], so that the measured samples can be
+/// told apart from warm-up iterations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BenchSettings {
+    pub sample_size: Option<usize>,
+    /// Seconds.
+    pub measurement_time: Option<f64>,
+    /// Seconds.
+    pub warm_up_time: Option<f64>,
+    /// Go back to the state after setup before every iteration or batch.
+    pub reset: Option<Reset>,
+}
+
+impl BenchSettings {
+    /// Fills anything unset in `self` from `fallback`.
+    pub fn or(&self, fallback: &BenchSettings) -> BenchSettings {
+        BenchSettings {
+            sample_size: self.sample_size.or(fallback.sample_size),
+            measurement_time: self.measurement_time.or(fallback.measurement_time),
+            warm_up_time: self.warm_up_time.or(fallback.warm_up_time),
+            reset: self.reset.or(fallback.reset),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.sample_size.is_some_and(|n| n < 10) {
+            bail!("sample_size must be at least 10");
+        }
+        for seconds in [self.measurement_time, self.warm_up_time]
+            .into_iter()
+            .flatten()
+        {
+            if !(seconds > 0.0 && seconds.is_finite()) {
+                bail!("durations must be a positive number of seconds");
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn sample_size(&self) -> usize {
+        self.sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE)
+    }
+
+    pub(crate) fn apply(&self, group: &mut BenchmarkGroup<'_, WallTime>) {
+        group.sample_size(self.sample_size());
+        if let Some(seconds) = self.measurement_time {
+            group.measurement_time(Duration::from_secs_f64(seconds));
+        }
+        if let Some(seconds) = self.warm_up_time {
+            group.warm_up_time(Duration::from_secs_f64(seconds));
+        }
+    }
+}
+
+/// An ordered set of workloads to hand to [`crate::bench_middleware`].
+pub struct WorkloadRegistry<M: Middleware + 'static> {
+    workloads: Vec<(Box<dyn Workload<M>>, BenchSettings)>,
+    defaults: BenchSettings,
+    snapshots: Option<Arc<dyn Snapshots>>,
+}
+
+impl<M: Middleware + 'static> Default for WorkloadRegistry<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Middleware + 'static> WorkloadRegistry<M> {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self {
+            workloads: Vec::new(),
+            defaults: BenchSettings::default(),
+            snapshots: None,
+        }
+    }
+
+    /// The registry of workloads that ship with this crate.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(StatefulCall::default())
+            .register(StatelessCall::default())
+            .register(Create::default())
+            .register(Lookups::default());
+        registry
+    }
+
+    /// A workload per EVM precompile, calling it through a contract.
+    pub fn precompiles() -> Self {
+        let mut registry = Self::new();
+        for precompile in Precompile::ALL {
+            registry.register(PrecompileCall::new(precompile));
+        }
+        registry
+    }
+
+    /// Workloads moving tokens between accounts, with `spender` sending
+    /// some of the transactions from an account of its own, such as one from
+    /// [`crate::Backend::account`].
+    pub fn transfers(spender: Arc<M>) -> Self {
+        let mut registry = Self::new();
+        registry.register(TransferFrom::new(spender));
+        registry
+    }
+
+    /// Every workload that can be picked by name without a second account:
+    /// the built-in ones, then the precompile ones.
+    pub fn available() -> Self {
+        let mut registry = Self::builtin();
+        registry.append(Self::precompiles());
+        registry
+    }
+
+    /// Moves the workloads of `other` to the end of the registry.
+    pub fn append(&mut self, other: Self) -> &mut Self {
+        self.workloads.extend(other.workloads);
+        self
+    }
+
+    /// Adds a workload to the end of the registry.
+    pub fn register(&mut self, workload: impl Workload<M> + 'static) -> &mut Self {
+        self.register_with(workload, BenchSettings::default())
+    }
+
+    /// Adds a workload that is benchmarked with its own Criterion settings.
+    pub fn register_with(
+        &mut self,
+        workload: impl Workload<M> + 'static,
+        settings: BenchSettings,
+    ) -> &mut Self {
+        self.workloads.push((Box::new(workload), settings));
+        self
+    }
+
+    /// Keeps only the workloads with the given names, in the order given.
+    pub fn select(mut self, names: &[&str]) -> Result<Self> {
+        let mut selected = Vec::with_capacity(names.len());
+        for name in names {
+            let index = self
+                .workloads
+                .iter()
+                .position(|(workload, _)| workload.name() == *name)
+                .ok_or_else(|| anyhow!("unknown workload: {}", name))?;
+            selected.push(self.workloads.remove(index));
+        }
+        Ok(Self {
+            workloads: selected,
+            defaults: self.defaults,
+            snapshots: self.snapshots,
+        })
+    }
+
+    /// Settings for every workload that doesn't set its own.
+    pub fn defaults(&mut self, settings: BenchSettings) -> &mut Self {
+        self.defaults = settings;
+        self
+    }
+
+    /// Where to save and restore state for workloads with a [`Reset`].
+    pub fn snapshots(&mut self, snapshots: Arc<dyn Snapshots>) -> &mut Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.workloads
+            .iter()
+            .map(|(workload, _)| workload.name())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workloads.is_empty()
+    }
+
+    pub(crate) fn take_snapshots(&mut self) -> Option<Arc<dyn Snapshots>> {
+        self.snapshots.take()
+    }
+
+    /// Each workload along with its settings, after applying the defaults.
+    pub(crate) fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut Box<dyn Workload<M>>, BenchSettings)> {
+        let defaults = &self.defaults;
+        self.workloads
+            .iter_mut()
+            .map(move |(workload, settings)| (workload, settings.or(defaults)))
+    }
+}
+
+/// Builds the same workloads for any middleware, for runs that benchmark
+/// several middleware types against one backend.
+pub trait WorkloadFactory {
+    fn build<M: Middleware + 'static>(&self) -> Result<WorkloadRegistry<M>>;
+}